@@ -0,0 +1,62 @@
+//! Times removal of scattered selects, its undo and redo for growing collections.
+//! The time per element should stay about the same when the collection doubles
+//!
+//! Run with `cargo bench --bench batch`
+
+use std::time::{Duration, Instant};
+
+use vec_historic::VecHistoric;
+
+/// Runs `f` on a fresh collection of `len` elements with every 20th one selected
+fn measure(len: usize, f: impl Fn(&mut VecHistoric<u64>)) -> Duration {
+    let mut best = Duration::MAX;
+
+    for _ in 0..3 {
+        let mut b: VecHistoric<u64> = (0..len as u64).collect();
+
+        for index in (0..len).step_by(20) {
+            b.select(index);
+        }
+
+        let start = Instant::now();
+        f(&mut b);
+        best = best.min(start.elapsed());
+    }
+
+    best
+}
+
+fn report(name: &str, f: impl Fn(&mut VecHistoric<u64>)) {
+    for len in [250_000, 500_000, 1_000_000] {
+        let time = measure(len, &f);
+        let per_element = time.as_nanos() as f64 / len as f64;
+
+        println!("{name:<24} {len:>9} elements {:>10.2?} {per_element:>6.2} ns/element", time);
+    }
+}
+
+fn main() {
+    report("remove_selects", |b| {
+        b.remove_selects();
+    });
+
+    report("remove_selects_historic", |b| {
+        b.remove_selects_historic();
+    });
+
+    report("undo removal", |b| {
+        b.remove_selects_historic();
+        b.undo();
+    });
+
+    report("undo and redo removal", |b| {
+        b.remove_selects_historic();
+        b.undo();
+        b.redo();
+    });
+
+    report("move_selects_historic", |b| {
+        b.move_selects_historic(0);
+        b.undo();
+    });
+}
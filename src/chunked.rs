@@ -0,0 +1,455 @@
+use std::fmt;
+use std::iter::Flatten;
+use std::mem;
+use std::ops::{Index, IndexMut, Range};
+
+use super::storage::Storage;
+
+/// Elements in a leaf after which the leaf is split in two
+const MAX_LEAF: usize = 512;
+
+/// Children of a branch after which the branch is split in two
+const MAX_BRANCH: usize = 16;
+
+/// Node of the tree, all leaves are at the same depth
+#[derive(Clone)]
+enum Node<T> {
+    Leaf(Vec<T>),
+    Branch { len: usize, children: Vec<Node<T>> }, // `len` counts elements of the subtree
+}
+
+/// Sequence of elements kept in a B-tree of chunks (a rope). Access, insertion and removal take
+/// logarithmic time plus moving elements of a single chunk, so long sequences stay cheap to edit anywhere
+#[derive(Clone)]
+pub struct ChunkedVec<T> {
+    root: Node<T>,
+}
+
+/// Returns the child holding the position `index` and the position in it.
+/// If `end` is true the position after the last element of a child belongs to it
+fn child_at<T>(children: &[Node<T>], index: usize, end: bool) -> (usize, usize) {
+    let mut index = index;
+
+    for (k, child) in children.iter().enumerate() {
+        let len = child.len();
+
+        if index < len || (end && index == len) {
+            return (k, index);
+        }
+
+        index -= len;
+    }
+
+    unreachable!()
+}
+
+/// Joins an underfull child with its neighbour, the pair is split back evenly if it doesn't fit one node
+fn rebalance<T>(children: &mut Vec<Node<T>>, k: usize) {
+    if children.len() < 2 {
+        return;
+    }
+
+    let k = k.min(children.len() - 2);
+    let right = children.remove(k + 1);
+    let left = mem::replace(&mut children[k], Node::Leaf(Vec::new()));
+
+    let (left, right) = left.join(right);
+    children[k] = left;
+
+    if let Some(right) = right {
+        children.insert(k + 1, right);
+    }
+}
+
+impl<T> Node<T> {
+    /// Creates a branch counting elements of `children`
+    fn branch(children: Vec<Node<T>>) -> Self {
+        let len = children.iter().map(Node::len).sum();
+        return Node::Branch { len, children };
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(values) => values.len(),
+            Node::Branch { len, .. } => *len,
+        }
+    }
+
+    /// Returns true if the node is smaller than a node other than the root should be
+    #[inline(always)]
+    fn is_underfull(&self) -> bool {
+        match self {
+            Node::Leaf(values) => values.len() < MAX_LEAF / 2,
+            Node::Branch { children, .. } => children.len() < MAX_BRANCH / 2,
+        }
+    }
+
+    fn get(&self, index: usize) -> &T {
+        match self {
+            Node::Leaf(values) => return &values[index],
+            Node::Branch { children, .. } => {
+                let (k, index) = child_at(children, index, false);
+                return children[k].get(index);
+            }
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut T {
+        match self {
+            Node::Leaf(values) => return &mut values[index],
+            Node::Branch { children, .. } => {
+                let (k, index) = child_at(children, index, false);
+                return children[k].get_mut(index);
+            }
+        }
+    }
+
+    /// Inserts `value` at `index` and returns the right half of the node if it overflowed
+    fn insert(&mut self, index: usize, value: T) -> Option<Node<T>> {
+        match self {
+            Node::Leaf(values) => {
+                values.insert(index, value);
+
+                if values.len() <= MAX_LEAF {
+                    return None;
+                }
+
+                return Some(Node::Leaf(values.split_off(values.len() / 2)));
+            }
+            Node::Branch { len, children } => {
+                let (k, index) = child_at(children, index, true);
+                *len += 1;
+
+                if let Some(right) = children[k].insert(index, value) {
+                    children.insert(k + 1, right);
+                }
+
+                if children.len() <= MAX_BRANCH {
+                    return None;
+                }
+
+                let right = Node::branch(children.split_off(children.len() / 2));
+                *len -= right.len();
+
+                return Some(right);
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        match self {
+            Node::Leaf(values) => return values.remove(index),
+            Node::Branch { len, children } => {
+                let (k, index) = child_at(children, index, false);
+                let value = children[k].remove(index);
+                *len -= 1;
+
+                if children[k].is_underfull() {
+                    rebalance(children, k);
+                }
+
+                return value;
+            }
+        }
+    }
+
+    /// Removes the `range` of the node and appends its elements to `out` in their order
+    fn drain_into(&mut self, range: Range<usize>, out: &mut Vec<T>) {
+        let (len, children) = match self {
+            Node::Leaf(values) => {
+                out.extend(values.drain(range));
+                return;
+            }
+            Node::Branch { len, children } => (len, children),
+        };
+
+        *len -= range.len();
+
+        let mut start = 0;
+        let mut k = 0;
+
+        while k < children.len() && start < range.end {
+            let end = start + children[k].len();
+
+            if end <= range.start {
+                start = end;
+                k += 1;
+                continue;
+            }
+
+            let from = range.start.max(start) - start;
+            let to = range.end.min(end) - start;
+
+            if to - from == end - start {
+                children.remove(k).into_values(out);
+            } else {
+                children[k].drain_into(from..to, out);
+                k += 1;
+            }
+
+            start = end;
+        }
+
+        // only the children at the bounds of the range are left underfull
+        while children.len() > 1 {
+            let Some(k) = children.iter().position(Node::is_underfull) else {
+                break;
+            };
+
+            rebalance(children, k);
+        }
+    }
+
+    /// Joins the node with its right neighbour of the same depth.
+    /// Returns the right half too if the joined node would overflow
+    fn join(self, right: Node<T>) -> (Node<T>, Option<Node<T>>) {
+        match (self, right) {
+            (Node::Leaf(mut left), Node::Leaf(mut right)) => {
+                left.append(&mut right);
+
+                if left.len() <= MAX_LEAF {
+                    return (Node::Leaf(left), None);
+                }
+
+                let right = left.split_off(left.len() / 2);
+                return (Node::Leaf(left), Some(Node::Leaf(right)));
+            }
+            (Node::Branch { children: mut left, .. }, Node::Branch { children: mut right, .. }) => {
+                left.append(&mut right);
+
+                if left.len() <= MAX_BRANCH {
+                    return (Node::branch(left), None);
+                }
+
+                let right = left.split_off(left.len() / 2);
+                return (Node::branch(left), Some(Node::branch(right)));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Appends all elements of the node to `out`
+    fn into_values(self, out: &mut Vec<T>) {
+        match self {
+            Node::Leaf(mut values) => out.append(&mut values),
+            Node::Branch { children, .. } => children.into_iter().for_each(|child| child.into_values(out)),
+        }
+    }
+
+    fn into_leaves(self, out: &mut Vec<Vec<T>>) {
+        match self {
+            Node::Leaf(values) => out.push(values),
+            Node::Branch { children, .. } => children.into_iter().for_each(|child| child.into_leaves(out)),
+        }
+    }
+
+    fn leaves<'a>(&'a self, out: &mut Vec<&'a [T]>) {
+        match self {
+            Node::Leaf(values) => out.push(values),
+            Node::Branch { children, .. } => children.iter().for_each(|child| child.leaves(out)),
+        }
+    }
+
+    fn leaves_mut<'a>(&'a mut self, out: &mut Vec<&'a mut [T]>) {
+        match self {
+            Node::Leaf(values) => out.push(values),
+            Node::Branch { children, .. } => children.iter_mut().for_each(|child| child.leaves_mut(out)),
+        }
+    }
+}
+
+impl<T> ChunkedVec<T> {
+    /// Creates an empty sequence.
+    pub fn new() -> Self {
+        return Self {
+            root: Node::Leaf(Vec::new()),
+        };
+    }
+
+    /// Lowers the tree while the root has a single child
+    fn shrink(&mut self) {
+        while let Node::Branch { children, .. } = &mut self.root {
+            self.root = match children.len() {
+                0 => Node::Leaf(Vec::new()),
+                1 => children.pop().unwrap(),
+                _ => return,
+            };
+        }
+    }
+
+    #[inline(always)]
+    fn assert_index(&self, index: usize) {
+        let len = self.root.len();
+        assert!(index < len, "index out of bounds: the len is {len} but the index is {index}");
+    }
+}
+
+impl<T> Default for ChunkedVec<T> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChunkedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_list().entries(Storage::iter(self)).finish();
+    }
+}
+
+impl<T> Index<usize> for ChunkedVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.assert_index(index);
+        return self.root.get(index);
+    }
+}
+
+impl<T> IndexMut<usize> for ChunkedVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.assert_index(index);
+        return self.root.get_mut(index);
+    }
+}
+
+impl<T> Extend<T> for ChunkedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let len = Storage::len(self);
+        self.insert_many(len, iter);
+    }
+}
+
+impl<T> FromIterator<T> for ChunkedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut chunked = Self::new();
+        chunked.extend(iter);
+
+        return chunked;
+    }
+}
+
+impl<T> IntoIterator for ChunkedVec<T> {
+    type Item = T;
+    type IntoIter = Flatten<std::vec::IntoIter<Vec<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut leaves = vec![];
+        self.root.into_leaves(&mut leaves);
+
+        return leaves.into_iter().flatten();
+    }
+}
+
+impl<T> Storage<T> for ChunkedVec<T> {
+    type Iter<'a>
+        = Flatten<std::vec::IntoIter<&'a [T]>>
+    where
+        T: 'a;
+
+    type IterMut<'a>
+        = Flatten<std::vec::IntoIter<&'a mut [T]>>
+    where
+        T: 'a;
+
+    type Drain<'a>
+        = std::vec::IntoIter<T>
+    where
+        T: 'a;
+
+    /// The capacity isn't reserved
+    fn with_capacity(_cap: usize) -> Self {
+        return Self::new();
+    }
+
+    fn len(&self) -> usize {
+        return self.root.len();
+    }
+
+    fn push_back(&mut self, value: T) {
+        let len = Storage::len(self);
+        Storage::insert(self, len, value);
+    }
+
+    fn push_front(&mut self, value: T) {
+        Storage::insert(self, 0, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        if Storage::is_empty(self) {
+            return None;
+        }
+
+        let len = Storage::len(self);
+        return Some(Storage::remove(self, len - 1));
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if Storage::is_empty(self) {
+            return None;
+        }
+
+        return Some(Storage::remove(self, 0));
+    }
+
+    fn insert(&mut self, index: usize, value: T) {
+        let len = Storage::len(self);
+        assert!(index <= len, "insertion index (is {index}) should be <= len (is {len})");
+
+        if let Some(right) = self.root.insert(index, value) {
+            let left = mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::branch(vec![left, right]);
+        }
+    }
+
+    fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
+        let len = Storage::len(self);
+        assert!(index <= len, "insertion index (is {index}) should be <= len (is {len})");
+
+        for (k, value) in iter.into_iter().enumerate() {
+            Storage::insert(self, index + k, value);
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        self.assert_index(index);
+
+        let value = self.root.remove(index);
+        self.shrink();
+
+        return value;
+    }
+
+    /// Elements are removed at once, the iterator owns them
+    fn drain(&mut self, range: Range<usize>) -> Self::Drain<'_> {
+        let len = Storage::len(self);
+        assert!(range.start <= range.end && range.end <= len, "range {range:?} is out of bounds of {len}");
+
+        let mut drained: Vec<T> = Vec::with_capacity(range.len());
+
+        if !range.is_empty() {
+            self.root.drain_into(range, &mut drained);
+            self.shrink();
+        }
+
+        return drained.into_iter();
+    }
+
+    fn clear(&mut self) {
+        self.root = Node::Leaf(Vec::new());
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        let mut leaves = vec![];
+        self.root.leaves(&mut leaves);
+
+        return leaves.into_iter().flatten();
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        let mut leaves = vec![];
+        self.root.leaves_mut(&mut leaves);
+
+        return leaves.into_iter().flatten();
+    }
+}
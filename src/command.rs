@@ -0,0 +1,109 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::ops::Range;
+
+use super::defines::{Action, ChangeEvent};
+use super::storage::{Elements, Storage, StorageElements};
+use super::vec_historic::VecHistoric;
+
+/// User-defined undoable operation on elements of the collection, it's kept in history as `Action::Custom`.
+/// A command changes elements in place, the count of elements must stay the same
+pub trait HistoricCommand<T>: HistoricCommandBase<T> + Debug + Send + Sync {
+    /// Makes the operation
+    fn apply(&mut self, data: &mut dyn Elements<T>);
+
+    /// Brings the elements back to the state before `apply`
+    fn revert(&mut self, data: &mut dyn Elements<T>);
+
+    /// Absorbs the next command which was already applied, so undo reverts both at once.
+    /// Returns false if the commands can't be merged
+    fn merge_with(&mut self, next: &dyn HistoricCommand<T>) -> bool {
+        let _ = next;
+        return false;
+    }
+
+    /// Returns positions of elements changed by the command in a collection of length `len`
+    fn affected_range(&self, len: usize) -> Range<usize> {
+        return 0..len;
+    }
+
+    /// Returns a short description of the command
+    fn describe(&self) -> String {
+        return "command".to_string();
+    }
+}
+
+/// Object-safe helpers of `HistoricCommand`, implemented for every `Clone` command
+pub trait HistoricCommandBase<T> {
+    fn clone_box(&self) -> Box<dyn HistoricCommand<T>>;
+
+    /// Returns the command as `Any` for downcasting in `merge_with`
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T, C: HistoricCommand<T> + Clone + 'static> HistoricCommandBase<T> for C {
+    fn clone_box(&self) -> Box<dyn HistoricCommand<T>> {
+        return Box::new(self.clone());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+impl<T> Clone for Box<dyn HistoricCommand<T>> {
+    fn clone(&self) -> Self {
+        return self.clone_box();
+    }
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Applies a command and returns true if it was merged into the previous command
+    /// Creates an action in history sequence unless the command is merged, it's merged only when
+    /// the previous action is a command and nothing can be redone.
+    /// Commands can't be serialized or journaled, an attached journal fails and isn't written
+    /// until the command leaves history and the journal is compacted
+    pub fn apply_command_historic(&mut self, command: impl HistoricCommand<T> + 'static) -> bool {
+        let mut command: Box<dyn HistoricCommand<T>> = Box::new(command);
+        command.apply(&mut StorageElements(&mut self.data));
+
+        if self.merge_command(command.as_ref()) {
+            self.notify(ChangeEvent::Replaced(command.affected_range(self.data.len())));
+            return true;
+        }
+
+        self.push_action(Action::Custom(command));
+        return false;
+    }
+
+    /// Merges an applied command into the last pushed command
+    fn merge_command(&mut self, command: &dyn HistoricCommand<T>) -> bool {
+        let last = match self.groups.last_mut() {
+            Some(group) => group.last_mut(),
+            None if self.redo_history.is_empty() => self.history.back_mut(),
+            None => None,
+        };
+
+        let Some(Action::Custom(last)) = last else {
+            return false;
+        };
+
+        if !last.merge_with(command) {
+            return false;
+        }
+
+        // the content of the current state changed, save point and checkpoints don't match it anymore
+        if self.groups.is_empty() {
+            self.renew_current_state();
+
+            if let Some(meta) = self.meta.pending.take() {
+                self.meta.entries.insert(self.ids.current(), meta);
+            }
+
+            #[cfg(feature = "journal")]
+            self.journal_stale();
+        }
+
+        return true;
+    }
+}
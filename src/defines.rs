@@ -1,13 +1,58 @@
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use gapbuf::GapBuffer;
+
+use super::command::HistoricCommand;
+use super::storage::Storage;
 use super::vec_historic::VecHistoric;
 
-// pub struct Drain<'a, T: 'a> {
-//     pub(super) inner: &'a mut VecHistoric<T>,
-//     pub(super) idx: usize,
-//     pub(super) len: usize,
-// }
+/// Draining iterator made by `drain`, the range is removed before the iterator is made and it owns the elements
+pub struct Drain<'a, T: 'a, S: Storage<T> + 'a = GapBuffer<T>> {
+    pub(super) iter: std::vec::IntoIter<T>,
+    pub(super) marker: PhantomData<&'a mut S>,
+}
+
+/// Draining iterator made by `drain_iter_historic`, yields clones of the elements.
+/// The whole range is removed and recorded as an `Action::Remove` on drop
+pub struct HistoricDrain<'a, T: Clone, S: Storage<T> = GapBuffer<T>> {
+    pub(super) inner: &'a mut VecHistoric<T, S>,
+    pub(super) range: Range<usize>,
+    pub(super) front: usize,
+    pub(super) back: usize,
+}
+
+pub struct IntoIter<T, S: Storage<T> = GapBuffer<T>> {
+    pub(super) iter: <S as IntoIterator>::IntoIter,
+    pub(super) len: usize, // the storage iterator may be not exact size
+    pub(super) marker: PhantomData<T>,
+}
+
+/// Mutable access to an element which records an `Action::Replace` on drop if the element was accessed mutably
+pub struct HistoricMut<'a, T: Clone, S: Storage<T> = GapBuffer<T>> {
+    pub(super) inner: &'a mut VecHistoric<T, S>,
+    pub(super) index: usize,
+    pub(super) old: Option<T>,
+    pub(super) written: bool, // set by `deref_mut`
+}
+
+/// Read-only view of a past state of the collection made by `version_view`.
+/// Elements are looked up through the stored actions, nothing is copied
+pub struct HistoricView<'a, T, S = GapBuffer<T>> {
+    pub(super) data: &'a S,
+    pub(super) steps: Vec<(&'a Action<T>, usize)>, // actions from the oldest with the length before their change
+    pub(super) len: usize,
+}
 
-pub struct IntoIter<T> {
-    pub(super) inner: VecHistoric<T>,
+/// Iterator over elements of `HistoricView`
+pub struct ViewIter<'v, 'a, T, S = GapBuffer<T>> {
+    pub(super) view: &'v HistoricView<'a, T, S>,
+    pub(super) front: usize,
+    pub(super) back: usize,
 }
 
 pub type Iter<'a, T> = std::iter::Chain<std::slice::Iter<'a, T>, std::slice::Iter<'a, T>>;
@@ -16,24 +61,42 @@ pub type IterMut<'a, T> = std::iter::Chain<std::slice::IterMut<'a, T>, std::slic
 // pub type RemoveData<T> = (Vec<(usize, T)>); // index, element
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RemoveData<T> {
     pub indecies: Vec<usize>,
     pub values: Vec<T>
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MoveData {
-    pub dest_index: usize,
-    pub indecies: Vec<usize>,
+    pub dest_index: usize, // position of the first moved element after the move
+    pub indecies: Vec<usize>, // ascending positions of moved elements before the move
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsertData {
     pub index: usize,
     pub amount: usize, // amount of inserted elements
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplaceData<T> {
+    pub index: usize,
+    pub values: Vec<T>, // replaced elements starting from `index`
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PermuteData {
+    pub index: usize,      // start of the reordered block
+    pub order: Vec<usize>, // handling puts the element at `index + order[k]` to `index + k`
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action<T> {
     Remove(RemoveData<T>),
     Move(MoveData),
@@ -42,4 +105,143 @@ pub enum Action<T> {
     PopBack(T),
     PushFront,
     PopFront(T),
+    Reinsert(Vec<usize>), // indecies of put back elements, inverse of Remove
+    MoveBack(MoveData),   // inverse of Move
+    Group(Vec<Action<T>>), // actions in order they were made, undone as one step
+    Replace(ReplaceData<T>),
+    Permute(PermuteData), // its own inverse with the inverse order
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Box<dyn HistoricCommand<T>>), // applied command, handling reverts it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    CustomBack(Box<dyn HistoricCommand<T>>), // inverse of Custom
+}
+
+/// Untracked mutation of the collection in coordinates of the state before it
+#[derive(Clone, Debug)]
+pub(crate) enum Edit {
+    Insert { index: usize, amount: usize },
+    Remove(Vec<usize>), // ascending indecies of removed elements
+}
+
+/// Limits of history sequence, the oldest actions are evicted first when any of them is exceeded.
+/// Actions of redo sequence and parked branches of the undo tree are counted too and evicted before history sequence.
+/// The newest action is evicted only by the next one, even if it exceeds the limits alone
+#[derive(Clone, Debug)]
+pub struct HistoryLimits<T> {
+    pub max_actions: Option<usize>,
+    pub max_values: Option<usize>, // elements kept by actions
+    pub max_bytes: Option<usize>,  // sum of `size_of` of elements kept by actions
+    pub size_of: fn(&T) -> usize,
+    pub keep_evicted: bool, // evicted elements are kept until `take_evicted` instead of dropping
+}
+
+/// Coalescing of consecutive insertions or removals at adjacent positions into one history entry, e.g. typed characters.
+/// An action merges into the last entry of history only if nothing can be redone and the state wasn't changed since it
+#[derive(Clone, Debug, Default)]
+pub struct MergePolicy {
+    pub window: Option<Duration>, // time after the previous action within which the next one merges, None means any time
+    pub key: Option<u64>,         // actions merge while the key stays the same, e.g. a word or a cursor session
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MergeState {
+    pub(crate) policy: MergePolicy,
+    pub(crate) last: Option<(NodeId, Instant, Option<u64>)>, // entry which can absorb the next action, its time and key
+    pub(crate) offset: usize, // position of the first element removed by the last merged action among elements of the entry
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct HistoryBudget<T> {
+    pub(crate) limits: HistoryLimits<T>,
+    pub(crate) values: usize, // elements kept by history sequence
+    pub(crate) bytes: usize,
+    pub(crate) evicted: Vec<T>,
+}
+
+/// Optional description of a history entry, attached by `with_meta`
+#[derive(Clone, Debug, Default)]
+pub struct ActionMeta {
+    pub label: Option<String>, // e.g. `Typing` for an `Undo Typing` menu item
+    pub timestamp: Option<SystemTime>,
+    pub user: Option<Arc<dyn Any + Send + Sync>>, // any value, e.g. the author, read by `user_as`
+}
+
+/// Entry of history or redo sequence, it derefs to its action
+#[derive(Debug)]
+pub struct HistoryEntry<'a, T> {
+    pub action: &'a Action<T>, // the action which undoes or redoes the entry
+    pub meta: Option<&'a ActionMeta>,
+}
+
+/// Metadata of history entries
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HistoryMeta {
+    pub(crate) entries: HashMap<NodeId, ActionMeta>, // by the state reached by the entry
+    pub(crate) pending: Option<ActionMeta>,          // attached to the next entry
+    pub(crate) live: usize,                          // entries left by the last pruning
+}
+
+/// Identifier of a state of the collection reached by history, also a node in the undo tree.
+/// Ids are never reused, a state keeps its id until it's dropped from history
+pub type NodeId = usize;
+
+#[derive(Clone, Debug)]
+pub(crate) struct UndoNode<T> {
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) children: Vec<NodeId>, // in creation order
+    pub(crate) action: Option<Action<T>>, // redo action of a node out of the active branch
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct UndoTree<T> {
+    pub(crate) nodes: HashMap<NodeId, UndoNode<T>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct HistoryIds {
+    pub(crate) root: NodeId, // state before the first action of history sequence
+    pub(crate) next: NodeId,
+    pub(crate) history: VecDeque<NodeId>, // states reached by the actions of history sequence
+    pub(crate) redo: Vec<NodeId>,         // states reached by the actions of redo sequence
+}
+
+/// Options of the action journal
+#[cfg(feature = "journal")]
+#[derive(Clone, Debug)]
+pub struct JournalOptions {
+    pub compact_every: Option<usize>, // records after which the journal is compacted into a snapshot
+    pub sync: bool,                   // every record is synced to the disk
+}
+
+/// Journal attached to the collection, clones of the collection aren't attached
+pub(crate) struct JournalSlot<T, S> {
+    #[cfg(feature = "journal")]
+    pub(crate) journal: Option<Box<crate::journal::Journal<T, S>>>,
+    pub(crate) marker: PhantomData<fn() -> (T, S)>,
+}
+
+
+/// Change of the collection reported to observers.
+/// Positions of removed elements are counted before the removal, all other positions after the change
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeEvent {
+    Inserted(Range<usize>),
+    Removed(Range<usize>),
+    Moved { from: Vec<usize>, to: Vec<usize> }, // the element at `from[i]` is at `to[i]` after the move
+    Replaced(Range<usize>),
+    SelectionChanged, // elements were selected or deselected, shifting of selects isn't reported
+    Undone,           // sent after the changes made by an undo step
+    Redone,           // sent after the changes made by a redo step
+}
+
+/// Identifier of an observer subscribed by `on_change`
+pub type ObserverId = usize;
+
+pub(crate) type Observer = Box<dyn FnMut(&ChangeEvent) + Send + Sync>;
+
+/// Observers of the collection, clones of the collection have no observers
+pub(crate) struct Observers {
+    pub(crate) callbacks: Vec<(ObserverId, Observer)>,
+    pub(crate) next: ObserverId,
+    pub(crate) selection: bool, // the set of selected elements changed since the last notification
 }
@@ -1,8 +1,20 @@
+use std::any::Any;
+use std::collections::VecDeque;
 use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use crate::RemoveData;
+use crate::{HistoryLimits, RemoveData};
 
-use super::defines::IntoIter;
+use super::defines::{
+    Action, ActionMeta, Drain, HistoricDrain, HistoricMut, HistoryEntry, HistoryIds, IntoIter, JournalSlot, NodeId, Observers, ReplaceData,
+};
+use super::private::values_of_action;
+#[cfg(feature = "journal")]
+use super::defines::JournalOptions;
+use super::storage::Storage;
 use super::vec_historic::VecHistoric;
 
 impl<T> RemoveData<T> {
@@ -14,53 +26,335 @@ impl<T> RemoveData<T> {
     }
 }
 
-impl<T> IntoIter<T> {
-    pub fn new(inner: VecHistoric<T>) -> Self {
-        return Self { inner };
+impl<T> Action<T> {
+    /// Returns a short description of what applying the action does, e.g. `remove 2 elements at 3..5`.
+    /// Actions of history describe undo steps, actions of redo sequence describe redo steps
+    pub fn describe(&self) -> String {
+        match self {
+            Action::PushBack => return "pop back element".to_string(),
+            Action::PopBack(_) => return "push back element".to_string(),
+            Action::PushFront => return "pop front element".to_string(),
+            Action::PopFront(_) => return "push front element".to_string(),
+            Action::Insert(data) => {
+                return format!("remove {} elements at {}..{}", data.amount, data.index, data.index + data.amount);
+            }
+            Action::Remove(data) => return format!("insert {} elements", data.values.len()),
+            Action::Reinsert(indecies) => return format!("remove {} elements", indecies.len()),
+            Action::Move(data) => {
+                return format!("move {} elements back from {}", data.indecies.len(), data.dest_index);
+            }
+            Action::MoveBack(data) => {
+                return format!("move {} elements to {}", data.indecies.len(), data.dest_index);
+            }
+            Action::Group(actions) => return format!("group of {} actions", actions.len()),
+            Action::Replace(data) => {
+                let end = data.index + data.values.len();
+                return format!("replace {} elements at {}..{end}", data.values.len(), data.index);
+            }
+            Action::Permute(data) => {
+                let end = data.index + data.order.len();
+                return format!("reorder {} elements at {}..{end}", data.order.len(), data.index);
+            }
+            Action::Custom(command) => return format!("revert {}", command.describe()),
+            Action::CustomBack(command) => return format!("apply {}", command.describe()),
+        }
+    }
+}
+
+impl<T> HistoryLimits<T> {
+    /// Creates limits with no limit set, bytes are counted by `size_of::<T>()`
+    pub fn new() -> Self {
+        Self {
+            max_actions: None,
+            max_values: None,
+            max_bytes: None,
+            size_of: |_| std::mem::size_of::<T>(),
+            keep_evicted: false,
+        }
+    }
+
+    /// Returns true if any limit is exceeded
+    #[inline(always)]
+    pub fn exceeded(&self, actions: usize, values: usize, bytes: usize) -> bool {
+        self.max_actions.is_some_and(|max| actions > max)
+            || self.max_values.is_some_and(|max| values > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+    }
+}
+
+impl<T> Default for HistoryLimits<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl ActionMeta {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        return self;
+    }
+
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        return self;
+    }
+
+    /// Sets the timestamp to the current time
+    pub fn stamped(self) -> Self {
+        return self.with_timestamp(SystemTime::now());
+    }
+
+    pub fn with_user<U: Any + Send + Sync>(mut self, user: U) -> Self {
+        self.user = Some(Arc::new(user));
+        return self;
+    }
+
+    /// Returns the user value if it's of type `U`
+    pub fn user_as<U: Any>(&self) -> Option<&U> {
+        return self.user.as_ref()?.downcast_ref::<U>();
+    }
+}
+
+impl<'a, T> HistoryEntry<'a, T> {
+    /// Returns the label of the entry if it has one
+    pub fn label(&self) -> Option<&'a str> {
+        return self.meta?.label.as_deref();
+    }
+
+    /// Returns addresses of elements kept by the action of the entry
+    pub fn values(&self) -> Vec<&'a T> {
+        return values_of_action(self.action);
+    }
+}
+
+impl<T> Clone for HistoryEntry<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for HistoryEntry<'_, T> {}
+
+impl<T> Deref for HistoryEntry<'_, T> {
+    type Target = Action<T>;
+
+    fn deref(&self) -> &Action<T> {
+        self.action
+    }
+}
+
+#[cfg(feature = "journal")]
+impl Default for JournalOptions {
+    fn default() -> Self {
+        return JournalOptions {
+            compact_every: Some(1024),
+            sync: false,
+        };
+    }
+}
+
+impl<T, S> JournalSlot<T, S> {
+    pub(crate) fn new() -> Self {
+        return JournalSlot {
+            #[cfg(feature = "journal")]
+            journal: None,
+            marker: PhantomData,
+        };
+    }
+}
+
+impl<T, S> Clone for JournalSlot<T, S> {
+    fn clone(&self) -> Self {
+        return JournalSlot::new();
+    }
+}
+
+impl<T, S> std::fmt::Debug for JournalSlot<T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(feature = "journal")]
+        if let Some(journal) = self.journal.as_ref() {
+            return write!(f, "JournalSlot({:?})", journal.path);
+        }
+
+        return write!(f, "JournalSlot(None)");
+    }
+}
+
+impl Observers {
+    pub(crate) fn new() -> Self {
+        return Observers {
+            callbacks: Vec::new(),
+            next: 0,
+            selection: false,
+        };
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        return Observers::new();
+    }
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "Observers({})", self.callbacks.len());
+    }
+}
+
+impl HistoryIds {
+    pub(crate) fn new() -> Self {
+        Self {
+            root: 0,
+            next: 1,
+            history: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Returns the current state
+    #[inline(always)]
+    pub(crate) fn current(&self) -> NodeId {
+        self.history.back().copied().unwrap_or(self.root)
+    }
+
+    /// Returns a new unique id
+    #[inline(always)]
+    pub(crate) fn make_id(&mut self) -> NodeId {
+        let id = self.next;
+        self.next += 1;
+        return id;
+    }
+
+    /// Returns true if the state is reachable by history or redo sequences
+    pub(crate) fn contains(&self, id: NodeId) -> bool {
+        id == self.root || self.history.contains(&id) || self.redo.contains(&id)
+    }
+}
+
+impl<T: Clone, S: Storage<T>> Deref for HistoricMut<'_, T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner.data[self.index]
+    }
+}
+
+impl<T: Clone, S: Storage<T>> DerefMut for HistoricMut<'_, T, S> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.written = true;
+        &mut self.inner.data[self.index]
     }
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T: Clone, S: Storage<T>> Drop for HistoricMut<'_, T, S> {
+    fn drop(&mut self) {
+        if !self.written {
+            return;
+        }
+
+        let replace_data = ReplaceData {
+            index: self.index,
+            values: vec![self.old.take().unwrap()],
+        };
+
+        self.inner.push_action(Action::Replace(replace_data));
+    }
+}
+
+impl<T, S: Storage<T>> IntoIter<T, S> {
+    pub fn new(inner: VecHistoric<T, S>) -> Self {
+        let len = inner.data.len();
+        return Self {
+            iter: inner.data.into_iter(),
+            len,
+            marker: PhantomData,
+        };
+    }
+}
+
+impl<T, S: Storage<T>> Iterator for IntoIter<T, S> {
     type Item = T;
 
     fn next(&mut self) -> Option<T> {
-        self.inner.pop_front()
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.iter.next()
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.inner.len();
+        (self.len, Some(self.len))
+    }
+}
+impl<T, S: Storage<T>> ExactSizeIterator for IntoIter<T, S> {}
+impl<T, S: Storage<T>> FusedIterator for IntoIter<T, S> {}
+impl<T, S: Storage<T>> DoubleEndedIterator for IntoIter<T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.iter.next_back()
+    }
+}
+
+impl<T, S: Storage<T>> Iterator for Drain<'_, T, S> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+impl<T, S: Storage<T>> ExactSizeIterator for Drain<'_, T, S> {}
+impl<T, S: Storage<T>> FusedIterator for Drain<'_, T, S> {}
+impl<T, S: Storage<T>> DoubleEndedIterator for Drain<'_, T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T: Clone, S: Storage<T>> Iterator for HistoricDrain<'_, T, S> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.inner.data[self.front - 1].clone())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
         (len, Some(len))
     }
 }
-impl<T> ExactSizeIterator for IntoIter<T> {}
-impl<T> FusedIterator for IntoIter<T> {}
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T: Clone, S: Storage<T>> ExactSizeIterator for HistoricDrain<'_, T, S> {}
+impl<T: Clone, S: Storage<T>> FusedIterator for HistoricDrain<'_, T, S> {}
+impl<T: Clone, S: Storage<T>> DoubleEndedIterator for HistoricDrain<'_, T, S> {
     fn next_back(&mut self) -> Option<T> {
-        self.inner.pop_back()
-    }
-}
-
-// impl<'a, T> Iterator for Drain<'a, T> {
-//     type Item = T;
-//     fn next(&mut self) -> Option<T> {
-//         if self.len == 0 {
-//             self.inner.clear_history();
-//             self.inner.clear_selects();
-//             None
-//         } else {
-//             self.len -= 1;
-//             Some(self.inner.data.remove(self.idx))
-//         }
-//     }
-//     fn size_hint(&self) -> (usize, Option<usize>) {
-//         (self.len, Some(self.len))
-//     }
-// }
-// impl<'a, T> Drop for Drain<'a, T> {
-//     fn drop(&mut self) {
-//         let len = self.len;
-//         self.nth(len);
-//     }
-// }
-
-// impl<'a, T> ExactSizeIterator for Drain<'a, T> {}
-// impl<'a, T> FusedIterator for Drain<'a, T> {}
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.inner.data[self.back].clone())
+    }
+}
+impl<T: Clone, S: Storage<T>> Drop for HistoricDrain<'_, T, S> {
+    fn drop(&mut self) {
+        if self.range.is_empty() {
+            return;
+        }
+
+        let action = self.inner.take_range(self.range.clone());
+
+        self.inner.break_merge();
+        self.inner.push_action(action);
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+/// Error of the fallible `try_*` methods, the collection is left unchanged when it's returned
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VecHistoricError {
+    /// Index is out of bounds of the collection
+    IndexOutOfBounds { index: usize, len: usize },
+    /// Range is out of bounds of the collection or its start is greater than its end
+    RangeOutOfBounds { start: usize, end: usize, len: usize },
+    /// Count of given elements doesn't match the expected one
+    LengthMismatch { expected: usize, found: usize },
+    /// History sequence is empty
+    NothingToUndo,
+    /// Redo sequence is empty
+    NothingToRedo,
+    /// The operation isn't allowed while a group of actions is open
+    GroupOpen,
+    /// The action can't be handled by the collection of its length
+    InvalidAction,
+    /// The entry at `index` of history sequence depends on the entry reverted by `undo_at`
+    Conflict { index: usize },
+}
+
+impl fmt::Display for VecHistoricError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VecHistoricError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} is out of bounds of length {len}")
+            }
+            VecHistoricError::RangeOutOfBounds { start, end, len } => {
+                write!(f, "range {start}..{end} is out of bounds of length {len}")
+            }
+            VecHistoricError::LengthMismatch { expected, found } => {
+                write!(f, "expected {expected} elements, found {found}")
+            }
+            VecHistoricError::NothingToUndo => write!(f, "history is empty"),
+            VecHistoricError::NothingToRedo => write!(f, "redo sequence is empty"),
+            VecHistoricError::GroupOpen => write!(f, "a group of actions is open"),
+            VecHistoricError::InvalidAction => write!(f, "the action doesn't fit the collection"),
+            VecHistoricError::Conflict { index } => {
+                write!(f, "history entry {index} depends on the reverted one")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VecHistoricError {}
@@ -1,37 +1,47 @@
 use std::collections::VecDeque;
 
 use gapbuf::GapBuffer;
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 
+use super::defines::{HistoryIds, HistoryMeta, JournalSlot, Observers};
+use super::storage::Storage;
 use super::vec_historic::VecHistoric;
 
-impl<T> VecHistoric<T> {
-    pub fn from_data(data: GapBuffer<T>) -> Self {
-        Self {
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Creates a collection which keeps elements in `data`.
+    pub fn from_data(data: S) -> Self {
+        return Self {
             data,
             selects: IndexSet::new(),
             history: VecDeque::new(),
-        }
+            redo_history: Vec::new(),
+            tree: None,
+            groups: Vec::new(),
+            budget: None,
+            merge: None,
+            ids: HistoryIds::new(),
+            checkpoints: IndexMap::new(),
+            saved: Some(0),
+            rebase: false,
+            journal: JournalSlot::new(),
+            observers: Observers::new(),
+            meta: HistoryMeta::default(),
+        };
     }
+}
 
+/// Constructors of the default storage, other storages are made by `from_data`, `default` or `collect`
+impl<T> VecHistoric<T> {
     /// Creates an empty collection.
     #[inline(always)]
     pub fn new() -> Self {
-        return VecHistoric {
-            selects: IndexSet::new(),
-            data: GapBuffer::new(),
-            history: VecDeque::new(),
-        };
+        return Self::from_data(GapBuffer::new());
     }
 
     /// Creates an empty collection with the specified capacity (if supported).
     #[inline(always)]
     pub fn with_capacity(cap: usize) -> Self {
-        return VecHistoric {
-            selects: IndexSet::new(),
-            data: GapBuffer::with_capacity(cap),
-            history: VecDeque::new(),
-        };
+        return Self::from_data(GapBuffer::with_capacity(cap));
     }
 
     /// Creates a collection from a slice by cloning each element.
@@ -40,21 +50,13 @@ impl<T> VecHistoric<T> {
     where
         T: Clone,
     {
-        return VecHistoric {
-            selects: IndexSet::new(),
-            data: GapBuffer::from_iter(slice.iter().cloned()),
-            history: VecDeque::new(),
-        };
+        return Self::from_data(GapBuffer::from_iter(slice.iter().cloned()));
     }
 
     /// Creates a collection from an array.
     #[inline(always)]
     pub fn from_array<const N: usize>(arr: [T; N]) -> Self {
-        return VecHistoric {
-            selects: IndexSet::new(),
-            data: GapBuffer::from_iter(arr),
-            history: VecDeque::new(),
-        };
+        return Self::from_data(GapBuffer::from_iter(arr));
     }
 
     /// Creates a collection with `n` clones of a given value.
@@ -63,11 +65,7 @@ impl<T> VecHistoric<T> {
     where
         T: Clone,
     {
-        return VecHistoric {
-            selects: IndexSet::new(),
-            data: GapBuffer::from_iter(std::iter::repeat(value).take(n)),
-            history: VecDeque::new(),
-        };
+        return Self::from_data(GapBuffer::from_iter(std::iter::repeat_n(value, n)));
     }
 
     /// Converts a slice into a collection (alias to `from_slice`).
@@ -79,3 +77,10 @@ impl<T> VecHistoric<T> {
         return Self::from_slice(slice);
     }
 }
+
+impl<T, S: Storage<T>> Default for VecHistoric<T, S> {
+    #[inline(always)]
+    fn default() -> Self {
+        return Self::from_data(S::default());
+    }
+}
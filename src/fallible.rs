@@ -0,0 +1,269 @@
+use std::ops::RangeBounds;
+
+use super::defines::{Action, Drain, HistoricMut, HistoryEntry};
+use super::error::VecHistoricError;
+use super::private::*;
+use super::selective::{change_edits, shape};
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Returns an error if `index` isn't a position of an element
+    #[inline(always)]
+    fn check_index(&self, index: usize) -> Result<(), VecHistoricError> {
+        if index >= self.data.len() {
+            return Err(VecHistoricError::IndexOutOfBounds {
+                index,
+                len: self.data.len(),
+            });
+        }
+
+        return Ok(());
+    }
+
+    /// Returns an error if elements can't be inserted at `index`
+    #[inline(always)]
+    fn check_insert_index(&self, index: usize) -> Result<(), VecHistoricError> {
+        if index > self.data.len() {
+            return Err(VecHistoricError::IndexOutOfBounds {
+                index,
+                len: self.data.len(),
+            });
+        }
+
+        return Ok(());
+    }
+
+    /// Returns an error if any select is out of bounds
+    fn check_selects(&self) -> Result<(), VecHistoricError> {
+        for &index in self.selects.iter() {
+            self.check_index(index)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Checks that every action of history and redo sequences can be handled in turn
+    /// and every select is in bounds, returns the first found problem
+    pub fn validate_history(&self) -> Result<(), VecHistoricError> {
+        let sequences = [
+            self.history.iter().rev().collect::<Vec<_>>(),
+            self.redo_history.iter().rev().collect::<Vec<_>>(),
+        ];
+
+        for sequence in sequences {
+            let mut len = self.data.len();
+
+            for action in sequence {
+                validate_action(action, len)?;
+                len = (len as isize - action_len_delta(action)) as usize;
+            }
+        }
+
+        return self.check_selects();
+    }
+
+    /// Applies an action the way undo does and returns its inverse, applying the inverse brings the collection back.
+    /// Selects follow their elements, history is rebased through the change like through other untracked mutations
+    /// or wiped for preventing index shifting if rebasing is disabled or the action holds a command
+    /// Returns an error if the action doesn't fit the collection
+    pub fn apply_action(&mut self, action: Action<T>) -> Result<Action<T>, VecHistoricError> {
+        let len = self.data.len();
+        validate_action(&action, len)?;
+
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        let inverse = self.handle_action(action);
+
+        // positions touched by a command are unknown
+        match shape(&inverse).and_then(|shape| change_edits(&shape, len)) {
+            Some(edits) => {
+                for (edit, len) in edits {
+                    self.rebase_or_wipe(edit, len);
+                }
+            }
+            None => self.wipe_history(),
+        }
+
+        self.notify_done(&inverse);
+
+        return Ok(inverse);
+    }
+
+    /// Applies an action the way undo does
+    /// Creates an action in history sequence, undo applies the inverse of the action
+    /// Returns an error if the action doesn't fit the collection
+    pub fn apply_action_historic(&mut self, action: Action<T>) -> Result<(), VecHistoricError> {
+        validate_action(&action, self.data.len())?;
+
+        let inverse = self.handle_action(action);
+        self.push_action(inverse);
+
+        return Ok(());
+    }
+
+    /// Inserts an element at position `index` within the vector
+    /// Returns an error if `index > len`
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), VecHistoricError> {
+        self.check_insert_index(index)?;
+        self.insert(index, value);
+        return Ok(());
+    }
+
+    /// Inserts elements or iterator at position `index` within the vector
+    /// Returns an error if `index > len`
+    pub fn try_insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) -> Result<(), VecHistoricError> {
+        self.check_insert_index(index)?;
+        self.insert_many(index, iter);
+        return Ok(());
+    }
+
+    /// Removes an element from the VecHistoric and returns it
+    /// Returns an error if `index >= len`
+    pub fn try_remove(&mut self, index: usize) -> Result<T, VecHistoricError> {
+        self.check_index(index)?;
+        return Ok(self.remove(index));
+    }
+
+    /// Creates a draining iterator that removes the specified range
+    /// Returns an error if the `range` is out of bounds
+    pub fn try_drain(&mut self, range: impl RangeBounds<usize>) -> Result<Drain<'_, T, S>, VecHistoricError> {
+        let range = try_resolve_range(range, self.data.len())?;
+        return Ok(self.drain(range));
+    }
+
+    /// Selects an element by index
+    /// Returns an error if `index >= len`
+    pub fn try_select(&mut self, index: usize) -> Result<(), VecHistoricError> {
+        self.check_index(index)?;
+        self.select(index);
+        return Ok(());
+    }
+
+    /// Returns the values of selected elements
+    /// Returns an error if any select is out of bounds
+    pub fn try_get_selected(&self) -> Result<Vec<&T>, VecHistoricError> {
+        self.check_selects()?;
+        return Ok(self.get_selected());
+    }
+
+    /// Undo last action in the collection and returns the undone entry with its metadata
+    /// Returns an error if history is empty or a group is open
+    pub fn try_undo(&mut self) -> Result<HistoryEntry<'_, T>, VecHistoricError> {
+        if !self.groups.is_empty() {
+            return Err(VecHistoricError::GroupOpen);
+        }
+
+        let action = self.history.back().ok_or(VecHistoricError::NothingToUndo)?;
+        validate_action(action, self.data.len())?;
+
+        return Ok(self.undo().unwrap());
+    }
+
+    /// Redo last undone action in the collection and returns the redone entry with its metadata
+    /// Returns an error if the redo sequence is empty or a group is open
+    pub fn try_redo(&mut self) -> Result<HistoryEntry<'_, T>, VecHistoricError> {
+        if !self.groups.is_empty() {
+            return Err(VecHistoricError::GroupOpen);
+        }
+
+        let action = self.redo_history.last().ok_or(VecHistoricError::NothingToRedo)?;
+        validate_action(action, self.data.len())?;
+
+        return Ok(self.redo().unwrap());
+    }
+
+    /// Inserts an element at position `index` within the VecHistoric
+    /// Creates an action in history sequence
+    /// Returns an error if `index > len`
+    pub fn try_insert_historic(&mut self, index: usize, value: T) -> Result<(), VecHistoricError> {
+        self.check_insert_index(index)?;
+        self.insert_historic(index, value);
+        return Ok(());
+    }
+
+    /// Inserts an elements or iterator at position `index` within the VecHistoric
+    /// Creates an action in history sequence
+    /// Returns an error if `index > len`
+    pub fn try_insert_many_historic(
+        &mut self,
+        index: usize,
+        iter: impl IntoIterator<Item = T>,
+    ) -> Result<(), VecHistoricError> {
+        self.check_insert_index(index)?;
+        self.insert_many_historic(index, iter);
+        return Ok(());
+    }
+
+    /// Removes selected elements and returns them
+    /// Returns an error if any select is out of bounds
+    pub fn try_remove_selects(&mut self) -> Result<Vec<T>, VecHistoricError> {
+        self.check_selects()?;
+        return Ok(self.remove_selects());
+    }
+
+    /// Removes selected elements and returns address of the removed elements
+    /// Creates an action in history sequence
+    /// Returns an error if any select is out of bounds
+    pub fn try_remove_selects_historic(&mut self) -> Result<&Vec<T>, VecHistoricError> {
+        self.check_selects()?;
+        return Ok(self.remove_selects_historic());
+    }
+
+    /// Moves selected elements before the element at position `to_index` counted before their removal
+    /// Returns an error if any select is out of bounds
+    pub fn try_move_selects(&mut self, to_index: usize) -> Result<(), VecHistoricError> {
+        self.check_selects()?;
+        self.move_selects(to_index);
+        return Ok(());
+    }
+
+    /// Moves selected elements before the element at position `to_index` counted before their removal
+    /// Creates an action in history sequence
+    /// Returns an error if any select is out of bounds
+    pub fn try_move_selects_historic(&mut self, to_index: usize) -> Result<(), VecHistoricError> {
+        self.check_selects()?;
+        self.move_selects_historic(to_index);
+        return Ok(());
+    }
+
+    /// Replaces an element at position `index` and returns address of the replaced element
+    /// Creates an action in history sequence
+    /// Returns an error if `index >= len`
+    pub fn try_set_historic(&mut self, index: usize, value: T) -> Result<&T, VecHistoricError> {
+        self.check_index(index)?;
+        return Ok(self.set_historic(index, value));
+    }
+
+    /// Replaces elements in `range` by elements of the iterator and returns the replaced elements
+    /// Creates an action in history sequence. Nothing is recorded for an empty range
+    /// Returns an error if the `range` is out of bounds or the iterator yields other count of elements than `range` has
+    pub fn try_replace_range_historic(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        iter: impl IntoIterator<Item = T>,
+    ) -> Result<&[T], VecHistoricError> {
+        let range = try_resolve_range(range, self.data.len())?;
+        let values: Vec<T> = iter.into_iter().collect();
+
+        if values.len() != range.len() {
+            return Err(VecHistoricError::LengthMismatch {
+                expected: range.len(),
+                found: values.len(),
+            });
+        }
+
+        return Ok(self.replace_range_historic(range, values));
+    }
+
+    /// Returns mutable access to an element at position `index`
+    /// Returns an error if `index >= len`
+    pub fn try_get_mut_historic(&mut self, index: usize) -> Result<HistoricMut<'_, T, S>, VecHistoricError>
+    where
+        T: Clone,
+    {
+        self.check_index(index)?;
+        return Ok(self.get_mut_historic(index));
+    }
+}
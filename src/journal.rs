@@ -0,0 +1,550 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::defines::{Action, Edit, JournalOptions, MoveData, PermuteData};
+use super::error::VecHistoricError;
+use super::private::*;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+use super::view::{Found, has_command, locate};
+
+/// Record written in the journal, a line of the journal is `{crc32:08x} {record as json}`
+#[derive(Serialize)]
+#[serde(bound(serialize = "T: Serialize, S: Storage<T>"))]
+enum RecordRef<'a, T, S> {
+    Snapshot { rebase: bool, state: &'a VecHistoric<T, S> }, // the first record of the journal
+    Do(ForwardRef<'a, T>),                                 // redo form of a pushed action
+    Merge(ForwardRef<'a, T>),                              // redo form of an action merged into the last one
+    Undo,
+    Redo,
+    PushBack(&'a T), // untracked push_back, it keeps history in linear mode
+    Insert { index: usize, values: Vec<&'a T> },
+    Remove(&'a [usize]), // ascending positions of untracked removal
+}
+
+/// Borrowed redo form of an action, serialized as `Action`
+#[derive(Serialize)]
+#[serde(rename = "Action", bound(serialize = "T: Serialize"))]
+enum ForwardRef<'a, T> {
+    Remove { indecies: Vec<usize>, values: Vec<&'a T> },
+    Move(&'a MoveData),
+    PushBack,
+    PopBack(&'a T),
+    PushFront,
+    PopFront(&'a T),
+    Reinsert(&'a [usize]),
+    MoveBack(&'a MoveData),
+    Group(Vec<ForwardRef<'a, T>>),
+    Replace { index: usize, values: Vec<&'a T> },
+    Permute(PermuteData),
+}
+
+/// Owned form of `RecordRef` read while recovering
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>, S: Storage<T>"))]
+enum Record<T, S> {
+    Snapshot { rebase: bool, state: Box<VecHistoric<T, S>> },
+    Do(Action<T>),
+    Merge(Action<T>),
+    Undo,
+    Redo,
+    PushBack(T),
+    Insert { index: usize, values: Vec<T> },
+    Remove(Vec<usize>),
+}
+
+type Encode<T, S> = fn(&RecordRef<'_, T, S>) -> serde_json::Result<String>;
+
+/// Append-only journal of the collection
+pub(crate) struct Journal<T, S> {
+    pub(crate) path: PathBuf,
+    file: File,
+    options: JournalOptions,
+    records: usize,           // records after the snapshot
+    stale: bool,              // the collection was changed in a way which can't be journaled
+    failed: bool,             // a record was lost, nothing is written until `compact_journal` succeeds
+    error: Option<io::Error>, // the error which failed the journal
+    encode: Encode<T, S>,     // captured where `T: Serialize` is known
+}
+
+fn encode_record<T: Serialize, S: Storage<T>>(record: &RecordRef<'_, T, S>) -> serde_json::Result<String> {
+    return serde_json::to_string(record);
+}
+
+/// CRC-32 (IEEE) of `bytes`
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    return !crc;
+}
+
+/// Returns a line of the journal for a json record
+fn checksummed(json: &str) -> String {
+    return format!("{:08x} {json}\n", crc32(json.as_bytes()));
+}
+
+/// Returns the json record of a line, None if the line is torn or corrupted
+fn verified(line: &[u8]) -> Option<&[u8]> {
+    let (crc, json) = (line.get(..8)?, line.get(9..)?);
+
+    if line[8] != b' ' {
+        return None;
+    }
+
+    let crc = u32::from_str_radix(std::str::from_utf8(crc).ok()?, 16).ok()?;
+
+    match crc == crc32(json) {
+        true => Some(json),
+        false => None,
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, error);
+}
+
+impl<T, S> Journal<T, S> {
+    /// Appends a record to the journal
+    fn append(&mut self, json: serde_json::Result<String>) {
+        let result = json.map_err(io::Error::from).and_then(|json| {
+            self.file.write_all(checksummed(&json).as_bytes())?;
+
+            if self.options.sync {
+                self.file.sync_data()?;
+            }
+
+            return Ok(());
+        });
+
+        match result {
+            Ok(()) => self.records += 1,
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// Stops journaling until the journal is compacted by `compact_journal`
+    fn fail(&mut self, error: io::Error) {
+        self.failed = true;
+        self.error = Some(error);
+    }
+
+    /// Replaces the journal by a snapshot, the new file is renamed over the old one
+    fn compact(&mut self, snapshot: serde_json::Result<String>) -> io::Result<()> {
+        self.file = write_snapshot(&self.path, &snapshot?)?;
+        self.records = 0;
+        self.stale = false;
+        self.failed = false;
+        self.error = None;
+
+        return Ok(());
+    }
+
+    /// Returns true if the next record should be a snapshot
+    #[inline(always)]
+    fn is_due(&self) -> bool {
+        self.stale || self.options.compact_every.is_some_and(|every| self.records >= every)
+    }
+}
+
+/// Writes a journal which consists of a snapshot and returns it opened for appending
+fn write_snapshot(path: &Path, json: &str) -> io::Result<File> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(checksummed(json).as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    return OpenOptions::new().append(true).open(path);
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Appends a record made by `record` to the attached journal.
+    /// The journal is compacted instead if it's stale or has enough records, the snapshot includes the change
+    fn journal_record(&mut self, record: impl FnOnce(&mut Self, Encode<T, S>) -> serde_json::Result<String>) {
+        let Some(mut journal) = self.journal.journal.take() else {
+            return;
+        };
+
+        if !journal.failed {
+            if journal.is_due() {
+                let snapshot = (journal.encode)(&RecordRef::Snapshot {
+                    rebase: self.rebase,
+                    state: self,
+                });
+
+                if let Err(error) = journal.compact(snapshot) {
+                    journal.fail(error);
+                }
+            } else {
+                let json = record(self, journal.encode);
+                journal.append(json);
+            }
+        }
+
+        self.journal.journal = Some(journal);
+    }
+
+    /// Returns the element at `p` of the state before the changes whose inverses are `later`,
+    /// they're paired with the lengths before them from the oldest
+    fn element_before<'a>(&'a self, later: &[(&'a Action<T>, usize)], p: usize) -> &'a T {
+        let mut p = p;
+
+        for &(action, len) in later {
+            match locate(action, p, len) {
+                Found::At(next) => p = next,
+                Found::Kept(value) => return value,
+            }
+        }
+
+        return &self.data[p];
+    }
+
+    /// Returns the redo form of the change whose inverse is `action`, the data is left as is.
+    /// `later` are the changes made after it as in `element_before`, `len` is the length after the change
+    fn forward<'a>(&'a self, action: &'a Action<T>, later: &[(&'a Action<T>, usize)], len: usize) -> ForwardRef<'a, T> {
+        let element = |p: usize| self.element_before(later, p);
+
+        match action {
+            Action::PushBack => return ForwardRef::PopBack(element(len - 1)),
+            Action::PushFront => return ForwardRef::PopFront(element(0)),
+            Action::Insert(data) => {
+                let indecies: Vec<usize> = (data.index..data.index + data.amount).rev().collect();
+                let values = indecies.iter().map(|&index| element(index)).collect();
+
+                return ForwardRef::Remove { indecies, values };
+            }
+            Action::Reinsert(indecies) => {
+                let values = indecies.iter().map(|&index| element(index)).collect();
+                return ForwardRef::Remove { indecies: indecies.clone(), values };
+            }
+            Action::Remove(data) => return ForwardRef::Reinsert(&data.indecies),
+            Action::PopBack(_) => return ForwardRef::PushBack,
+            Action::PopFront(_) => return ForwardRef::PushFront,
+            Action::Move(data) => return ForwardRef::MoveBack(data),
+            Action::MoveBack(data) => return ForwardRef::Move(data),
+            Action::Replace(data) => {
+                let values = (data.index..data.index + data.values.len()).map(element).collect();
+                return ForwardRef::Replace { index: data.index, values };
+            }
+            Action::Permute(data) => {
+                return ForwardRef::Permute(PermuteData {
+                    index: data.index,
+                    order: invert_order(&data.order),
+                });
+            }
+            // handling a group goes from its last action, so the redo form starts from the last change
+            Action::Group(actions) => {
+                let mut later = later.to_vec();
+                let mut len = len;
+                let mut forwards = Vec::with_capacity(actions.len());
+
+                for action in actions.iter().rev() {
+                    forwards.push(self.forward(action, &later, len));
+
+                    len = (len as isize - action_len_delta(action)) as usize;
+                    later.insert(0, (action, len));
+                }
+
+                return ForwardRef::Group(forwards);
+            }
+            Action::Custom(_) | Action::CustomBack(_) => unreachable!(),
+        }
+    }
+
+    /// Encodes the redo form of the inverse `action` of the last change
+    fn encode_forward(&self, action: &Action<T>, encode: Encode<T, S>, merge: bool) -> serde_json::Result<String> {
+        if has_command(action) {
+            return Err(serde::ser::Error::custom("custom commands can't be journaled"));
+        }
+
+        let forward = self.forward(action, &[], self.data.len());
+
+        return match merge {
+            true => encode(&RecordRef::Merge(forward)),
+            false => encode(&RecordRef::Do(forward)),
+        };
+    }
+
+    /// Appends the last action of history to the journal
+    pub(super) fn journal_push(&mut self) {
+        self.journal_record(|historic, encode| {
+            return historic.encode_forward(historic.history.back().unwrap(), encode, false);
+        });
+    }
+
+    /// Encodes the inverse `action` of the last change before it's merged into the last action of history.
+    /// Returns None if no journal is attached
+    pub(super) fn journal_encode_merge(&self, action: &Action<T>) -> Option<serde_json::Result<String>> {
+        let journal = self.journal.journal.as_ref()?;
+        return Some(self.encode_forward(action, journal.encode, true));
+    }
+
+    /// Appends a record made by `journal_encode_merge` after the merge
+    pub(super) fn journal_merge(&mut self, json: serde_json::Result<String>) {
+        self.journal_record(move |_, _| json);
+    }
+
+    /// Appends an undo or a redo step to the journal
+    pub(super) fn journal_step(&mut self, undo: bool) {
+        self.journal_record(|_, encode| match undo {
+            true => encode(&RecordRef::Undo),
+            false => encode(&RecordRef::Redo),
+        });
+    }
+
+    /// Appends an untracked push_back to the journal, it's called after the mutation
+    pub(super) fn journal_push_back(&mut self) {
+        self.journal_record(|historic, encode| {
+            let last = historic.data.len() - 1;
+            return encode(&RecordRef::PushBack(&historic.data[last]));
+        });
+    }
+
+    /// Appends an untracked mutation to the journal.
+    /// Insertions are journaled after the mutation, removals may be journaled before it
+    pub(super) fn journal_edit(&mut self, edit: &Edit) {
+        let Some(journal) = self.journal.journal.as_mut() else {
+            return;
+        };
+
+        // actions of open groups aren't journaled until commit, so the edit can't be replayed over them
+        if self.groups.iter().any(|group| !group.is_empty()) {
+            journal.stale = true;
+        }
+
+        // a snapshot made now could miss a pending removal
+        if journal.stale || journal.failed {
+            return;
+        }
+
+        let json = match edit {
+            Edit::Insert { index, amount } => (journal.encode)(&RecordRef::Insert {
+                index: *index,
+                values: (*index..index + amount).map(|i| &self.data[i]).collect(),
+            }),
+            Edit::Remove(indecies) => (journal.encode)(&RecordRef::Remove(indecies)),
+        };
+
+        journal.append(json);
+    }
+
+    /// Marks the journal stale after a change which can't be journaled,
+    /// the next record compacts the journal into a snapshot
+    pub(super) fn journal_stale(&mut self) {
+        if let Some(journal) = self.journal.journal.as_mut() {
+            journal.stale = true;
+        }
+    }
+
+    /// Replays a record of the journal
+    fn replay(&mut self, record: Record<T, S>) -> Result<(), VecHistoricError> {
+        let len = self.data.len();
+
+        match record {
+            Record::Snapshot { rebase, state } => {
+                *self = *state;
+                self.rebase = rebase;
+            }
+            Record::Do(action) => {
+                validate_action(&action, len)?;
+
+                let inverse = self.handle_action(action);
+                self.push_action(inverse);
+            }
+            Record::Merge(action) => {
+                validate_action(&action, len)?;
+
+                let inverse = self.handle_action(action);
+                let len = self.data.len();
+                let adjacent = self.history.back().is_some_and(|last| are_adjacent(last, &inverse, len));
+
+                if !adjacent || !self.redo_history.is_empty() {
+                    self.handle_action(inverse);
+                    return Err(VecHistoricError::InvalidAction);
+                }
+
+                self.notify_done(&inverse);
+                self.merge_into_last(inverse);
+            }
+            Record::Undo => {
+                self.try_undo()?;
+            }
+            Record::Redo => {
+                self.try_redo()?;
+            }
+            Record::PushBack(value) => self.push_back(value),
+            Record::Insert { index, values } => self.try_insert_many(index, values)?,
+            Record::Remove(indecies) => {
+                let ascending = indecies.windows(2).all(|pair| pair[0] < pair[1]);
+
+                if !ascending || indecies.last().is_some_and(|&index| index >= len) {
+                    return Err(VecHistoricError::InvalidAction);
+                }
+
+                for &index in indecies.iter().rev() {
+                    self.data.remove(index);
+                }
+
+                self.untracked_edit(Edit::Remove(indecies), len);
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Sets options of the attached journal
+    pub fn set_journal_options(&mut self, options: JournalOptions) {
+        if let Some(journal) = self.journal.journal.as_mut() {
+            journal.options = options;
+        }
+    }
+
+    /// Returns true if a journal is attached
+    #[inline(always)]
+    pub fn is_journaled(&self) -> bool {
+        self.journal.journal.is_some()
+    }
+
+    /// Returns the error of a lost record or a failed write. The journal stays failed after it is taken,
+    /// nothing is written to it until `compact_journal` succeeds
+    pub fn take_journal_error(&mut self) -> Option<io::Error> {
+        return self.journal.journal.as_mut()?.error.take();
+    }
+
+    /// Returns true if a record of the attached journal was lost and it waits for `compact_journal`
+    #[inline(always)]
+    pub fn is_journal_failed(&self) -> bool {
+        self.journal.journal.as_ref().is_some_and(|journal| journal.failed)
+    }
+
+    /// Detaches the journal, the file is kept. Returns false if no journal is attached
+    pub fn close_journal(&mut self) -> bool {
+        return self.journal.journal.take().is_some();
+    }
+}
+
+impl<T: Serialize, S: Storage<T>> VecHistoric<T, S> {
+    /// Attaches an append-only journal at `path`, the file is replaced by a snapshot of the collection.
+    /// Afterwards every historic action, undo, redo and untracked insertion or removal is appended to it
+    /// Changes which can't be journaled (`clear_history`, `move_selects`, undo tree jumps, mutable access
+    /// to elements) make the next record a snapshot. Actions of open groups are journaled on commit.
+    /// Custom commands can't be journaled, the journal fails on them (see `is_journal_failed`)
+    pub fn open_journal(&mut self, path: impl AsRef<Path>, options: JournalOptions) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        let snapshot = serde_json::to_string(&RecordRef::Snapshot {
+            rebase: self.rebase,
+            state: self,
+        })?;
+
+        let file = write_snapshot(&path, &snapshot)?;
+
+        self.journal.journal = Some(Box::new(Journal {
+            path,
+            file,
+            options,
+            records: 0,
+            stale: false,
+            failed: false,
+            error: None,
+            encode: encode_record::<T, S>,
+        }));
+
+        return Ok(());
+    }
+
+    /// Rewrites the attached journal as a snapshot of the collection, a failed journal is written again after it
+    pub fn compact_journal(&mut self) -> io::Result<()> {
+        let Some(mut journal) = self.journal.journal.take() else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no journal is attached"));
+        };
+
+        let snapshot = (journal.encode)(&RecordRef::Snapshot {
+            rebase: self.rebase,
+            state: self,
+        });
+
+        let result = journal.compact(snapshot);
+        self.journal.journal = Some(journal);
+
+        return result;
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, S: Storage<T>> VecHistoric<T, S> {
+    /// Rebuilds the collection by replaying the journal at `path` and attaches the journal back with default options.
+    /// A torn or corrupted tail of the journal is truncated, the replay stops before it.
+    /// Undo tree branches, open groups, history limits, the merge policy, checkpoints and metadata of entries aren't restored
+    pub fn recover(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let mut bytes: Vec<u8> = vec![];
+        file.read_to_end(&mut bytes)?;
+
+        let mut historic: Option<Self> = None;
+        let mut records = 0;
+        let mut valid = 0;
+
+        while let Some(end) = bytes[valid..].iter().position(|&byte| byte == b'\n') {
+            let line = &bytes[valid..valid + end];
+
+            let Some(record) = verified(line).and_then(|json| serde_json::from_slice::<Record<T, S>>(json).ok()) else {
+                break;
+            };
+
+            match (historic.as_mut(), record) {
+                (None, Record::Snapshot { rebase, mut state }) => {
+                    state.rebase = rebase;
+                    historic = Some(*state);
+                }
+                (None, _) => return Err(invalid_data("journal doesn't start with a snapshot")),
+                (Some(historic), record) => {
+                    historic.replay(record).map_err(invalid_data)?;
+                    records += 1;
+                }
+            }
+
+            valid += end + 1;
+        }
+
+        let Some(mut historic) = historic else {
+            return Err(invalid_data("journal doesn't start with a snapshot"));
+        };
+
+        if valid < bytes.len() {
+            file.set_len(valid as u64)?;
+            file.sync_all()?;
+        }
+
+        drop(file);
+
+        historic.journal.journal = Some(Box::new(Journal {
+            file: OpenOptions::new().append(true).open(&path)?,
+            path,
+            options: JournalOptions::default(),
+            records,
+            stale: false,
+            failed: false,
+            error: None,
+            encode: encode_record::<T, S>,
+        }));
+
+        return Ok(historic);
+    }
+}
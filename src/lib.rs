@@ -7,6 +7,19 @@ mod private;
 mod defines;
 mod macros;
 mod vec_historic;
+mod undo_tree;
+mod rebase;
+mod selective;
+mod view;
+mod error;
+mod fallible;
+mod command;
+mod storage;
+mod chunked;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "journal")]
+mod journal;
 pub mod factory;
 pub mod defines_impl;
 pub mod traits_impl;
@@ -16,4 +29,8 @@ pub mod traits_impl;
 // pub use public::*;
 // pub use macros::*;
 pub use defines::*;
+pub use command::{HistoricCommand, HistoricCommandBase};
+pub use storage::{Elements, Storage};
+pub use chunked::ChunkedVec;
+pub use error::VecHistoricError;
 pub use vec_historic::VecHistoric;
\ No newline at end of file
@@ -1,8 +1,12 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Bound, Range, RangeBounds};
+use std::time::Instant;
 
-use crate::{RemoveData, vec_historic::VecHistoric};
+use crate::{InsertData, MoveData, PermuteData, RemoveData, ReplaceData, vec_historic::VecHistoric};
 
-use super::defines::Action;
+use super::defines::{Action, ChangeEvent, Edit, HistoryEntry, NodeId};
+use super::error::VecHistoricError;
+use super::storage::{Storage, StorageElements};
 
 #[inline(always)]
 pub fn take_values_from_action<T>(action: Action<T>) -> Vec<T> {
@@ -20,30 +24,294 @@ pub fn take_values_from_action<T>(action: Action<T>) -> Vec<T> {
         Action::PushBack => {}
         Action::PushFront => {}
         Action::Move(_) => {}
+        Action::Reinsert(_) => {}
+        Action::MoveBack(_) => {}
+        Action::Permute(_) => {}
+        Action::Group(actions) => {
+            return actions.into_iter().flat_map(take_values_from_action).collect();
+        }
+        Action::Replace(data) => {
+            return data.values;
+        }
+        Action::Custom(_) | Action::CustomBack(_) => {}
+    }
+
+    return vec![];
+}
+
+#[inline(always)]
+pub fn values_of_action<T>(action: &Action<T>) -> Vec<&T> {
+    match action {
+        Action::PopBack(element) => {
+            return vec![element];
+        }
+        Action::PopFront(element) => {
+            return vec![element];
+        }
+        Action::Remove(data) => {
+            return data.values.iter().collect();
+        }
+        Action::Group(actions) => {
+            return actions.iter().flat_map(values_of_action).collect();
+        }
+        Action::Replace(data) => {
+            return data.values.iter().collect();
+        }
+        _ => {}
     }
 
     return vec![];
 }
 
-impl<T> VecHistoric<T> {
+/// Returns true if `order` holds every position of `0..order.len()` once
+pub fn is_permutation(order: &[usize]) -> bool {
+    let mut seen = vec![false; order.len()];
+
+    for &k in order {
+        if k >= order.len() || std::mem::replace(&mut seen[k], true) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+/// Returns the order which brings back the block reordered by `order`
+pub fn invert_order(order: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; order.len()];
+
+    for (k, &from) in order.iter().enumerate() {
+        inverse[from] = k;
+    }
+
+    return inverse;
+}
+
+/// Returns the change of the collection length made by an action
+pub fn action_len_delta<T>(action: &Action<T>) -> isize {
+    match action {
+        Action::Remove(data) => -(data.values.len() as isize),
+        Action::Insert(data) => data.amount as isize,
+        Action::Reinsert(indecies) => indecies.len() as isize,
+        Action::PushBack | Action::PushFront => 1,
+        Action::PopBack(_) | Action::PopFront(_) => -1,
+        Action::Move(_) | Action::MoveBack(_) | Action::Replace(_) | Action::Permute(_) => 0,
+        Action::Custom(_) | Action::CustomBack(_) => 0,
+        Action::Group(actions) => actions.iter().map(action_len_delta).sum(),
+    }
+}
+
+/// Returns the start and the length of the block inserted by a change whose inverse is `action`,
+/// `len` is the length after the change
+fn inserted_run<T>(action: &Action<T>, len: usize) -> Option<(usize, usize)> {
+    match action {
+        Action::PushBack => return Some((len - 1, 1)),
+        Action::PushFront => return Some((0, 1)),
+        Action::Insert(data) => return Some((data.index, data.amount)),
+        _ => return None,
+    }
+}
+
+/// Returns the start and the length of the block removed by a change whose inverse is `action`
+/// if the removed elements were consecutive, `len` is the length after the change
+fn removed_run<T>(action: &Action<T>, len: usize) -> Option<(usize, usize)> {
+    match action {
+        Action::PopBack(_) => return Some((len, 1)),
+        Action::PopFront(_) => return Some((0, 1)),
+        Action::Remove(data) => {
+            let start = *data.indecies.last()?;
+            let consecutive = data.indecies.iter().rev().enumerate().all(|(i, &index)| index == start + i);
+
+            return consecutive.then_some((start, data.indecies.len()));
+        }
+        _ => return None,
+    }
+}
+
+/// Returns true if `last` and `next` are inverses of insertions or removals of adjacent blocks,
+/// `next` was made right after `last`, `len` is the length after both of them
+pub fn are_adjacent<T>(last: &Action<T>, next: &Action<T>, len: usize) -> bool {
+    if let Some((index, amount)) = inserted_run(next, len) {
+        // the new block is inserted inside or at a bound of the previous one
+        return inserted_run(last, len - amount).is_some_and(|(start, count)| start <= index && index <= start + count);
+    }
+
+    if let Some((index, amount)) = removed_run(next, len) {
+        // the new block surrounds or touches the place of the previous one
+        return removed_run(last, len + amount).is_some_and(|(start, _)| index <= start && start <= index + amount);
+    }
+
+    return false;
+}
+
+/// Merges adjacent `last` and `next` into one action, see `are_adjacent`.
+/// Returns also the position of the first element removed by `next` among elements of the merged action
+pub fn merge_adjacent<T>(last: Action<T>, next: Action<T>, len: usize) -> (Action<T>, usize) {
+    if let Some((_, amount)) = inserted_run(&next, len) {
+        let (start, count) = inserted_run(&last, len - amount).unwrap();
+
+        let merged = Action::Insert(InsertData {
+            index: start,
+            amount: count + amount,
+        });
+
+        return (merged, 0);
+    }
+
+    let (index, amount) = removed_run(&next, len).unwrap();
+    let (start, count) = removed_run(&last, len + amount).unwrap();
+
+    // values are kept in descending order of positions, the previous block is inside the new one
+    let split = index + amount - start;
+    let offset = if split > 0 { 0 } else { count };
+
+    let mut values = take_values_from_action(next);
+    let front = values.split_off(split);
+    values.extend(take_values_from_action(last));
+    values.extend(front);
+
+    let merged = Action::Remove(RemoveData {
+        indecies: (index..index + amount + count).rev().collect(),
+        values,
+    });
+
+    return (merged, offset);
+}
+
+/// Splits ascending positions into ranges of consecutive ones
+pub fn runs(positions: impl IntoIterator<Item = usize>) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = vec![];
+
+    for index in positions {
+        match runs.last_mut() {
+            Some(run) if run.end == index => run.end += 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+
+    return runs;
+}
+
+/// Makes an action reverting insertion of elements at `positions` (ascending)
+pub fn inserted_action<T>(positions: Vec<usize>) -> Action<T> {
+    let contiguous = positions.windows(2).all(|pair| pair[1] == pair[0] + 1);
+
+    match positions.first() {
+        Some(&index) if contiguous => Action::Insert(InsertData {
+            index,
+            amount: positions.len(),
+        }),
+        _ => Action::Reinsert(positions.into_iter().rev().collect()),
+    }
+}
+
+/// Checks that an action can be reverted by a collection of length `len`
+pub fn validate_action<T>(action: &Action<T>, len: usize) -> Result<(), VecHistoricError> {
+    let descending = |indecies: &[usize]| indecies.windows(2).all(|pair| pair[0] > pair[1]);
+    let ascending = |indecies: &[usize]| indecies.windows(2).all(|pair| pair[0] < pair[1]);
+    let fits = |index: usize, amount: usize| index.checked_add(amount).is_some_and(|end| end <= len);
+
+    let valid = match action {
+        Action::PushBack | Action::PushFront => len > 0,
+        Action::PopBack(_) | Action::PopFront(_) => true,
+        Action::Insert(data) => fits(data.index, data.amount),
+        Action::Remove(data) => {
+            // elements are put back at positions of the longer collection
+            data.indecies.len() == data.values.len()
+                && descending(&data.indecies)
+                && data.indecies.first().is_none_or(|&index| index < len + data.indecies.len())
+        }
+        Action::Reinsert(indecies) => descending(indecies) && indecies.first().is_none_or(|&index| index < len),
+        Action::Move(data) | Action::MoveBack(data) => {
+            ascending(&data.indecies)
+                && data.indecies.last().is_none_or(|&index| index < len)
+                && fits(data.dest_index, data.indecies.len())
+        }
+        Action::Replace(data) => fits(data.index, data.values.len()),
+        Action::Permute(data) => fits(data.index, data.order.len()) && is_permutation(&data.order),
+        Action::Custom(_) | Action::CustomBack(_) => true,
+        Action::Group(actions) => {
+            let mut len = len as isize;
+
+            // the last action of a group is reverted first
+            for action in actions.iter().rev() {
+                if len < 0 {
+                    return Err(VecHistoricError::InvalidAction);
+                }
+
+                validate_action(action, len as usize)?;
+                len -= action_len_delta(action);
+            }
+
+            len >= 0
+        }
+    };
+
+    match valid {
+        true => Ok(()),
+        false => Err(VecHistoricError::InvalidAction),
+    }
+}
+
+/// Converts `range` into bounds, returns an error if the range is out of bounds
+pub fn try_resolve_range(range: impl RangeBounds<usize>, len: usize) -> Result<Range<usize>, VecHistoricError> {
+    // a bound past `usize::MAX` is out of bounds of any collection
+    let start = match range.start_bound() {
+        Bound::Included(&start) => Some(start),
+        Bound::Excluded(&start) => start.checked_add(1),
+        Bound::Unbounded => Some(0),
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1),
+        Bound::Excluded(&end) => Some(end),
+        Bound::Unbounded => Some(len),
+    };
+
+    let (Some(start), Some(end)) = (start, end) else {
+        return Err(VecHistoricError::RangeOutOfBounds {
+            start: start.unwrap_or(usize::MAX),
+            end: end.unwrap_or(usize::MAX),
+            len,
+        });
+    };
+
+    if start > end || end > len {
+        return Err(VecHistoricError::RangeOutOfBounds { start, end, len });
+    }
+
+    return Ok(start..end);
+}
+
+/// Converts `range` into bounds of a collection with length `len`
+///
+/// # Panics
+/// Panics if the `range` is out of bounds.
+pub fn resolve_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    match try_resolve_range(range, len) {
+        Ok(range) => range,
+        Err(err) => panic!("{err}"),
+    }
+}
+
+/// Returns the count and the bytes of elements kept by an action
+#[inline(always)]
+pub fn action_weight<T>(action: &Action<T>, size_of: fn(&T) -> usize) -> (usize, usize) {
+    let values = values_of_action(action);
+    let bytes = values.iter().map(|&value| size_of(value)).sum();
+
+    return (values.len(), bytes);
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
     #[inline(always)]
     pub(super) fn compute_history_values_len(&self) -> usize {
         let mut len = 0;
 
-        for action in self.history.iter() {
-            match action {
-                Action::PopBack(_) => {
-                    len += 1;
-                }
-                Action::PopFront(_) => len += 1,
-                Action::Insert(_) => {}
-                Action::Remove(data) => {
-                    len += data.values.len();
-                }
-                Action::PushBack => {}
-                Action::PushFront => {}
-                Action::Move(_) => {}
-            }
+        let grouped = self.groups.iter().flatten();
+
+        for action in self.history.iter().chain(self.redo_history.iter()).chain(grouped) {
+            len += values_of_action(action).len();
         }
 
         return len;
@@ -58,76 +326,915 @@ impl<T> VecHistoric<T> {
         return selects;
     }
 
-    pub(super) fn handle_action(&mut self, action: Action<T>) -> Vec<T> {
+    /// Returns the move of selected elements before the element at `to_index` counted before their removal
+    pub(super) fn selects_move_data(&self, to_index: usize) -> MoveData {
+        let indecies = self.get_selects_sorted();
+        let to_index = to_index.min(self.data.len());
+
+        let dest_index = to_index - indecies.partition_point(|&index| index < to_index);
+
+        return MoveData { dest_index, indecies };
+    }
+
+    /// Maps every select by `f` keeping their order, selects mapped to [`None`] are dropped
+    fn remap_selects(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        if self.selects.is_empty() {
+            return;
+        }
+
+        let len = self.selects.len();
+        self.selects = self.selects.iter().filter_map(|&index| f(index)).collect();
+
+        if self.selects.len() < len {
+            self.observers.selection = true;
+        }
+    }
+
+    /// Selects elements affected by an action
+    pub(super) fn select_affected(&mut self, indecies: impl IntoIterator<Item = usize>) {
+        let len = self.selects.len();
+        self.selects.extend(indecies);
+
+        if self.selects.len() > len {
+            self.observers.selection = true;
+        }
+    }
+
+    /// Deselects all elements, observers are notified with the next change
+    pub(super) fn drop_selects(&mut self) {
+        if !self.selects.is_empty() {
+            self.selects.clear();
+            self.observers.selection = true;
+        }
+    }
+
+    /// Sends an event to every observer
+    pub(super) fn notify(&mut self, event: ChangeEvent) {
+        for (_, callback) in self.observers.callbacks.iter_mut() {
+            callback(&event);
+        }
+    }
+
+    /// Sends `SelectionChanged` if the set of selected elements changed since the last notification
+    pub(super) fn notify_selection(&mut self) {
+        if std::mem::take(&mut self.observers.selection) {
+            self.notify(ChangeEvent::SelectionChanged);
+        }
+    }
+
+    /// Notifies observers about an untracked mutation described by `edit`
+    pub(super) fn notify_edit(&mut self, edit: &Edit) {
+        if !self.observers.callbacks.is_empty() {
+            match edit {
+                Edit::Insert { amount: 0, .. } => {}
+                Edit::Insert { index, amount } => self.notify(ChangeEvent::Inserted(*index..index + amount)),
+                Edit::Remove(removed) => {
+                    // removing from the back keeps positions of the next ranges valid
+                    for run in runs(removed.iter().copied()).into_iter().rev() {
+                        self.notify(ChangeEvent::Removed(run));
+                    }
+                }
+            }
+        }
+
+        self.notify_selection();
+    }
+
+    /// Notifies observers about a change made by the collection, `action` is the inverse of the change
+    pub(super) fn notify_done(&mut self, action: &Action<T>) {
+        if !self.observers.callbacks.is_empty() {
+            self.notify_action(action, self.data.len());
+        }
+
+        self.notify_selection();
+    }
+
+    /// Sends events of a change described by its inverse `action`, `len` is the length after the change
+    fn notify_action(&mut self, action: &Action<T>, len: usize) {
         match action {
-            Action::PushBack => {
-                self.selects.clear();
+            Action::PushBack => self.notify(ChangeEvent::Inserted(len - 1..len)),
+            Action::PopBack(_) => self.notify(ChangeEvent::Removed(len..len + 1)),
+            Action::PushFront => self.notify(ChangeEvent::Inserted(0..1)),
+            Action::PopFront(_) => self.notify(ChangeEvent::Removed(0..1)),
+            Action::Insert(data) => self.notify(ChangeEvent::Inserted(data.index..data.index + data.amount)),
+            Action::Reinsert(indecies) => {
+                for run in runs(indecies.iter().rev().copied()) {
+                    self.notify(ChangeEvent::Inserted(run));
+                }
+            }
+            Action::Remove(data) => {
+                for run in runs(data.indecies.iter().rev().copied()).into_iter().rev() {
+                    self.notify(ChangeEvent::Removed(run));
+                }
+            }
+            Action::Move(data) => self.notify(ChangeEvent::Moved {
+                from: data.indecies.clone(),
+                to: (data.dest_index..data.dest_index + data.indecies.len()).collect(),
+            }),
+            Action::MoveBack(data) => self.notify(ChangeEvent::Moved {
+                from: (data.dest_index..data.dest_index + data.indecies.len()).collect(),
+                to: data.indecies.clone(),
+            }),
+            Action::Replace(data) => self.notify(ChangeEvent::Replaced(data.index..data.index + data.values.len())),
+            // the element at `index + order[k]` was at `index + k` before the change
+            Action::Permute(data) => self.notify(ChangeEvent::Moved {
+                from: (data.index..data.index + data.order.len()).collect(),
+                to: data.order.iter().map(|&k| data.index + k).collect(),
+            }),
+            Action::Custom(command) | Action::CustomBack(command) => {
+                self.notify(ChangeEvent::Replaced(command.affected_range(len)));
+            }
+            Action::Group(actions) => {
+                // the changes were made in order of the actions
+                let mut len = (len as isize - action_len_delta(action)) as usize;
+
+                for action in actions {
+                    len = (len as isize + action_len_delta(action)) as usize;
+                    self.notify_action(action, len);
+                }
+            }
+        }
+    }
+
+    /// Shifts selects through removal of elements at `removed` (ascending), selects of removed elements are dropped
+    pub(super) fn shift_selects_removed(&mut self, removed: &[usize]) {
+        self.remap_selects(|index| match removed.binary_search(&index) {
+            Ok(_) => None,
+            Err(below) => Some(index - below),
+        });
+    }
+
+    /// Shifts selects through insertion of elements, `inserted` (ascending) are positions of them after the insertion
+    pub(super) fn shift_selects_inserted(&mut self, inserted: &[usize]) {
+        // count of inserted elements before an old position `index` is count of `inserted[j] - j <= index`
+        let gaps: Vec<usize> = inserted.iter().enumerate().map(|(j, &i)| i - j).collect();
+
+        self.remap_selects(|index| Some(index + gaps.partition_point(|&gap| gap <= index)));
+    }
+
+    /// Shifts selects through a move of elements from positions `from` (ascending, before the move)
+    /// to positions `to` (ascending, after the move) keeping their order, selects of moved elements follow them
+    pub(super) fn shift_selects_moved(&mut self, from: &[usize], to: &[usize]) {
+        let gaps: Vec<usize> = to.iter().enumerate().map(|(j, &i)| i - j).collect();
+
+        self.remap_selects(|index| match from.binary_search(&index) {
+            Ok(rank) => Some(to[rank]),
+            Err(below) => {
+                let index = index - below;
+                Some(index + gaps.partition_point(|&gap| gap <= index))
+            }
+        });
+    }
+
+    /// Shifts selects through an untracked mutation
+    pub(super) fn shift_selects(&mut self, edit: &Edit) {
+        match edit {
+            Edit::Insert { index, amount } => {
+                self.remap_selects(|i| Some(if i >= *index { i + amount } else { i }));
+            }
+            Edit::Remove(removed) => self.shift_selects_removed(removed),
+        }
+    }
+
+    /// Pushes a new action made by the collection and notifies observers about the change
+    #[inline(always)]
+    pub(super) fn push_action(&mut self, action: Action<T>) {
+        self.notify_done(&action);
+        self.store_action(action);
+    }
+
+    /// Pushes a new action in history sequence or in the innermost open group.
+    /// The redo sequence is wiped since it doesn't follow the new action anymore,
+    /// in undo tree mode it's kept as a branch
+    pub(super) fn store_action(&mut self, action: Action<T>) {
+        if let Some(group) = self.groups.last_mut() {
+            group.push(action);
+            return;
+        }
+
+        let Some(action) = self.coalesce(action) else {
+            return;
+        };
+
+        let id = self.ids.make_id();
+
+        if self.tree.is_some() {
+            self.park_redo_line();
+
+            let parent = self.ids.current();
+            self.tree.as_mut().unwrap().add_node(id, parent);
+        } else {
+            self.redo_history.clear();
+            self.ids.redo.clear();
+        }
+
+        self.ids.history.push_back(id);
+        self.history_push(action);
+        self.attach_meta(id);
+
+        if let Some(merge) = self.merge.as_mut() {
+            merge.last = Some((id, Instant::now(), merge.policy.key));
+        }
+
+        #[cfg(feature = "journal")]
+        self.journal_push();
+
+        self.enforce_history_limits();
+    }
+
+    /// Merges a new action into the last entry of history if the merge policy allows it.
+    /// Returns the action back if it makes a new entry
+    fn coalesce(&mut self, action: Action<T>) -> Option<Action<T>> {
+        let Some(merge) = self.merge.as_ref() else {
+            return Some(action);
+        };
+
+        let now = Instant::now();
+
+        let fits = merge.last.is_some_and(|(id, at, key)| {
+            id == self.ids.current()
+                && key == merge.policy.key
+                && merge.policy.window.is_none_or(|window| now.duration_since(at) <= window)
+        });
+
+        let adjacent = self
+            .history
+            .back()
+            .is_some_and(|last| are_adjacent(last, &action, self.data.len()));
+
+        if !fits || !adjacent || !self.redo_history.is_empty() {
+            return Some(action);
+        }
+
+        #[cfg(feature = "journal")]
+        let json = self.journal_encode_merge(&action);
+
+        self.merge_into_last(action);
+
+        let merge = self.merge.as_mut().unwrap();
+        merge.last = Some((self.ids.current(), now, merge.policy.key));
+
+        #[cfg(feature = "journal")]
+        if let Some(json) = json {
+            self.journal_merge(json);
+        }
+
+        self.enforce_history_limits();
+        return None;
+    }
+
+    /// Makes the next action start a new entry of history even if the merge policy allows merging
+    #[inline(always)]
+    pub(super) fn break_merge(&mut self) {
+        if let Some(merge) = self.merge.as_mut() {
+            merge.last = None;
+        }
+    }
+
+    /// Takes out elements at ascending `positions` in one pass and returns them in ascending order.
+    /// Selects aren't shifted
+    pub(super) fn take_positions(&mut self, positions: &[usize]) -> Vec<T> {
+        let (Some(&first), Some(&last)) = (positions.first(), positions.last()) else {
+            return vec![];
+        };
+
+        let mut taken: Vec<T> = Vec::with_capacity(positions.len());
+        let mut kept: Vec<T> = Vec::with_capacity(last + 1 - first - positions.len());
+        let mut next = positions.iter().peekable();
+
+        // elements between the first and the last position are taken out once and the kept ones are put back
+        for (i, value) in (first..).zip(self.data.drain(first..last + 1)) {
+            if next.next_if_eq(&&i).is_some() {
+                taken.push(value);
+            } else {
+                kept.push(value);
+            }
+        }
+
+        self.data.insert_many(first, kept);
+
+        return taken;
+    }
+
+    /// Puts `values` at ascending `positions` counted after the insertion in one pass.
+    /// Selects aren't shifted
+    pub(super) fn put_positions(&mut self, positions: &[usize], values: impl IntoIterator<Item = T>) {
+        let (Some(&first), Some(&last)) = (positions.first(), positions.last()) else {
+            return;
+        };
+
+        // elements which end up between the first and the last position are merged with the values
+        let between = last + 1 - first - positions.len();
+        let mut kept = self.data.drain(first..first + between).collect::<Vec<T>>().into_iter();
+        let mut values = values.into_iter();
+        let mut next = positions.iter().peekable();
 
+        let merged: Vec<T> = (first..=last)
+            .map(|i| match next.next_if_eq(&&i) {
+                Some(_) => values.next().unwrap(),
+                None => kept.next().unwrap(),
+            })
+            .collect();
+
+        self.data.insert_many(first, merged);
+    }
+
+    /// Removes elements for which `remove` returns true in one pass, `remove` is called once per element in order.
+    /// Returns ascending positions of the removed elements with the elements, selects aren't shifted
+    pub(super) fn compact(&mut self, mut remove: impl FnMut(&T) -> bool) -> (Vec<usize>, Vec<T>) {
+        let len = self.data.len();
+
+        let Some(first) = (0..len).find(|&i| remove(&self.data[i])) else {
+            return (vec![], vec![]);
+        };
+
+        let mut removed: Vec<usize> = vec![first];
+        let mut values: Vec<T> = Vec::new();
+        let mut kept: Vec<T> = Vec::with_capacity(len - first - 1);
+
+        // the tail is taken out once and put back without the removed elements
+        let mut tail = self.data.drain(first..len);
+        values.extend(tail.next());
+
+        for (i, value) in (first + 1..).zip(tail) {
+            if remove(&value) {
+                removed.push(i);
+                values.push(value);
+            } else {
+                kept.push(value);
+            }
+        }
+
+        self.data.insert_many(first, kept);
+
+        return (removed, values);
+    }
+
+    /// Removes elements for which `remove` returns true like `compact` and creates one action in history sequence,
+    /// it isn't merged by the merge policy. Returns false if nothing was removed
+    pub(super) fn compact_historic(&mut self, remove: impl FnMut(&T) -> bool) -> bool {
+        let (mut indecies, mut values) = self.compact(remove);
+
+        if indecies.is_empty() {
+            return false;
+        }
+
+        self.shift_selects_removed(&indecies);
+
+        // `RemoveData` keeps descending positions
+        indecies.reverse();
+        values.reverse();
+
+        self.break_merge();
+        self.push_action(Action::Remove(RemoveData { indecies, values }));
+
+        return true;
+    }
+
+    /// Takes elements of `range` out and returns the inverse of the removal, selects of them are dropped
+    pub(super) fn take_range(&mut self, range: Range<usize>) -> Action<T> {
+        let mut values: Vec<T> = self.data.drain(range.clone()).collect();
+        values.reverse();
+
+        self.shift_selects_removed(&range.clone().collect::<Vec<_>>());
+
+        return Action::Remove(RemoveData {
+            indecies: range.rev().collect(),
+            values,
+        });
+    }
+
+    /// Returns elements removed by the range removal pushed last by `push_action` from the last one
+    pub(super) fn last_removed_range(&self) -> &[T] {
+        let action = match self.last_pushed_action() {
+            Action::Group(actions) => &actions[0],
+            action => action,
+        };
+
+        let Action::Remove(data) = action else {
+            unreachable!()
+        };
+
+        return &data.values;
+    }
+
+    /// Merges an action adjacent to the last entry of history into it, the current state gets a new id
+    pub(super) fn merge_into_last(&mut self, action: Action<T>) {
+        let last = self.history_pop().unwrap();
+        let (merged, offset) = merge_adjacent(last, action, self.data.len());
+
+        self.history_push(merged);
+        self.renew_current_state();
+
+        if let Some(merge) = self.merge.as_mut() {
+            merge.offset = offset;
+        }
+
+        if let Some(meta) = self.meta.pending.take() {
+            self.meta.entries.insert(self.ids.current(), meta);
+        }
+    }
+
+    /// Attaches metadata set by `with_meta` to the entry which reaches state `id`.
+    /// Metadata of dropped entries is pruned once it outgrows the metadata in use
+    pub(super) fn attach_meta(&mut self, id: NodeId) {
+        if let Some(meta) = self.meta.pending.take() {
+            self.meta.entries.insert(id, meta);
+        }
+
+        if self.meta.entries.len() <= self.meta.live * 2 + 16 {
+            return;
+        }
+
+        let linear: HashSet<NodeId> = match self.tree {
+            Some(_) => HashSet::new(),
+            None => self.ids.history.iter().chain(self.ids.redo.iter()).copied().collect(),
+        };
+
+        let tree = self.tree.as_ref();
+        self.meta.entries.retain(|id, _| match tree {
+            Some(tree) => tree.nodes.contains_key(id),
+            None => linear.contains(id),
+        });
+
+        self.meta.live = self.meta.entries.len();
+    }
+
+    /// Returns the entry of history sequence at `index`
+    #[inline(always)]
+    pub(super) fn history_entry(&self, index: usize) -> HistoryEntry<'_, T> {
+        return HistoryEntry {
+            action: &self.history[index],
+            meta: self.meta.entries.get(&self.ids.history[index]),
+        };
+    }
+
+    /// Returns the entry of redo sequence at `index`
+    #[inline(always)]
+    pub(super) fn redo_entry(&self, index: usize) -> HistoryEntry<'_, T> {
+        return HistoryEntry {
+            action: &self.redo_history[index],
+            meta: self.meta.entries.get(&self.ids.redo[index]),
+        };
+    }
+
+    /// Pushes an action in history sequence counting its weight for history limits
+    #[inline(always)]
+    pub(super) fn history_push(&mut self, action: Action<T>) {
+        if let Some(budget) = self.budget.as_mut() {
+            let (values, bytes) = action_weight(&action, budget.limits.size_of);
+            budget.values += values;
+            budget.bytes += bytes;
+        }
+
+        self.history.push_back(action);
+    }
+
+    /// Pops the last action of history sequence uncounting its weight for history limits
+    #[inline(always)]
+    pub(super) fn history_pop(&mut self) -> Option<Action<T>> {
+        let action = self.history.pop_back()?;
+
+        if let Some(budget) = self.budget.as_mut() {
+            let (values, bytes) = action_weight(&action, budget.limits.size_of);
+            budget.values -= values;
+            budget.bytes -= bytes;
+        }
+
+        return Some(action);
+    }
+
+    /// Drops the oldest action of history sequence, the state after it becomes the root.
+    /// Returns elements of the action and of the branches dropped with the old root
+    pub(super) fn drop_oldest_action(&mut self) -> Vec<T> {
+        let action = self.history.pop_front().unwrap();
+
+        if let Some(budget) = self.budget.as_mut() {
+            let (values, bytes) = action_weight(&action, budget.limits.size_of);
+            budget.values -= values;
+            budget.bytes -= bytes;
+        }
+
+        let mut values = take_values_from_action(action);
+
+        let old_root = self.ids.root;
+        self.ids.root = self.ids.history.pop_front().unwrap();
+
+        if let Some(tree) = self.tree.as_mut() {
+            for parked in tree.evict_root(old_root, self.ids.root) {
+                values.extend(take_values_from_action(parked));
+            }
+        }
+
+        return values;
+    }
+
+    /// Recounts elements and bytes kept by history sequence for history limits
+    pub(super) fn recount_budget(&mut self) {
+        let Some(budget) = self.budget.as_mut() else {
+            return;
+        };
+
+        budget.values = 0;
+        budget.bytes = 0;
+
+        for action in self.history.iter() {
+            let (values, bytes) = action_weight(action, budget.limits.size_of);
+            budget.values += values;
+            budget.bytes += bytes;
+        }
+    }
+
+    /// Returns count, elements and bytes of the actions kept by redo sequence and parked branches of the undo tree
+    fn spare_weight(&self, size_of: fn(&T) -> usize) -> (usize, usize, usize) {
+        let parked = self
+            .tree
+            .iter()
+            .flat_map(|tree| tree.nodes.values())
+            .filter_map(|node| node.action.as_ref());
+
+        let mut weight = (0, 0, 0);
+
+        for action in self.redo_history.iter().chain(parked) {
+            let (values, bytes) = action_weight(action, size_of);
+            weight = (weight.0 + 1, weight.1 + values, weight.2 + bytes);
+        }
+
+        return weight;
+    }
+
+    /// Drops the oldest parked branch of the undo tree, or the last action of redo sequence if there is none.
+    /// Returns the dropped actions
+    fn drop_spare_actions(&mut self) -> Vec<Action<T>> {
+        if let Some(tree) = self.tree.as_mut()
+            && let Some(branch) = tree.oldest_branch()
+        {
+            return tree.remove_subtree(branch);
+        }
+
+        // the next action to redo is the last one
+        let action = self.redo_history.remove(0);
+        let id = self.ids.redo.remove(0);
+
+        if let Some(tree) = self.tree.as_mut() {
+            tree.remove_subtree(id);
+        }
+
+        return vec![action];
+    }
+
+    /// Evicts actions while history limits are exceeded and returns their elements.
+    /// Parked branches of the undo tree go first from the oldest one, then redo sequence from its end,
+    /// then history sequence from the oldest action. The newest `keep` actions of history sequence aren't evicted
+    pub(super) fn evict_history(&mut self, keep: usize) -> Vec<T> {
+        let mut values: Vec<T> = vec![];
+
+        let Some(size_of) = self.budget.as_ref().map(|budget| budget.limits.size_of) else {
+            return values;
+        };
+
+        // counters of the budget cover history sequence only
+        let mut spare = self.spare_weight(size_of);
+
+        while let Some(budget) = self.budget.as_ref() {
+            let exceeded = budget.limits.exceeded(
+                self.history.len() + spare.0,
+                budget.values + spare.1,
+                budget.bytes + spare.2,
+            );
+
+            if !exceeded {
+                break;
+            }
+
+            if spare.0 > 0 {
+                for action in self.drop_spare_actions() {
+                    let (action_values, action_bytes) = action_weight(&action, size_of);
+                    spare = (spare.0 - 1, spare.1 - action_values, spare.2 - action_bytes);
+
+                    values.extend(take_values_from_action(action));
+                }
+
+                continue;
+            }
+
+            if self.history.len() <= keep {
+                break;
+            }
+
+            values.extend(self.drop_oldest_action());
+        }
+
+        return values;
+    }
+
+    /// Evicts the oldest actions while history limits are exceeded,
+    /// their elements are kept for `take_evicted` if the limits ask so.
+    /// The newest action stays until the next one since the caller may return its elements
+    pub(super) fn enforce_history_limits(&mut self) {
+        let values = self.evict_history(1);
+
+        if let Some(budget) = self.budget.as_mut()
+            && budget.limits.keep_evicted
+        {
+            budget.evicted.extend(values);
+        }
+    }
+
+    /// Returns true if the state can be reached by undo or redo
+    pub(super) fn is_state_reachable(&self, id: NodeId) -> bool {
+        match self.tree.as_ref() {
+            Some(tree) => tree.nodes.contains_key(&id),
+            None => self.ids.contains(id),
+        }
+    }
+
+    /// Gives the current state a new id after an untracked mutation,
+    /// checkpoints and the save point of it become invalid
+    #[inline(always)]
+    pub(super) fn renew_current_state(&mut self) {
+        self.renew_states(&[self.ids.current()]);
+    }
+
+    /// Gives reachable `states` new ids after an untracked mutation changed their content,
+    /// checkpoints and the save point of them become invalid
+    pub(super) fn renew_states(&mut self, states: &[NodeId]) {
+        let renewed: HashMap<NodeId, NodeId> = states.iter().map(|&old| (old, self.ids.make_id())).collect();
+        let renew = |id: &mut NodeId| {
+            if let Some(&new) = renewed.get(id) {
+                *id = new;
+            }
+        };
+
+        renew(&mut self.ids.root);
+        self.ids.history.iter_mut().for_each(renew);
+        self.ids.redo.iter_mut().for_each(renew);
+
+        for (&old, &new) in renewed.iter() {
+            if let Some(tree) = self.tree.as_mut() {
+                tree.rename(old, new);
+            }
+
+            if let Some(meta) = self.meta.entries.remove(&old) {
+                self.meta.entries.insert(new, meta);
+            }
+        }
+    }
+
+    /// Erases history and redo sequences and actions of open groups, returns their elements
+    pub(super) fn erase_history(&mut self) -> Vec<T> {
+        let mut values: Vec<T> = Vec::with_capacity(self.compute_history_values_len());
+
+        for group in self.groups.iter_mut() {
+            for action in group.drain(..) {
+                values.extend(take_values_from_action(action));
+            }
+        }
+
+        let current = self.ids.current();
+
+        let parked = match self.tree.as_mut() {
+            Some(tree) => tree.compact(current),
+            None => vec![],
+        };
+
+        self.ids.root = current;
+        self.ids.history.clear();
+        self.ids.redo.clear();
+
+        for action in self.history.drain(..).chain(self.redo_history.drain(..)).chain(parked) {
+            let taken_values = take_values_from_action(action);
+            values.extend(taken_values);
+        }
+
+        if let Some(budget) = self.budget.as_mut() {
+            budget.values = 0;
+            budget.bytes = 0;
+        }
+
+        self.meta.entries.clear();
+        self.meta.live = 0;
+
+        return values;
+    }
+
+    /// Wipes history for an untracked mutation to avoid index shifting
+    #[inline(always)]
+    pub(super) fn wipe_history(&mut self) {
+        self.erase_history();
+        self.renew_current_state();
+    }
+
+    /// Returns the action pushed last by `push_action`
+    #[inline(always)]
+    pub(super) fn last_pushed_action(&self) -> &Action<T> {
+        match self.groups.last() {
+            Some(group) => group.last().unwrap(),
+            None => self.history.back().unwrap(),
+        }
+    }
+
+    /// Returns the element removed by the pop pushed last by `push_action`, the pop may be merged into a removal
+    pub(super) fn last_pushed_value(&self) -> &T {
+        match self.last_pushed_action() {
+            Action::PopBack(value) | Action::PopFront(value) => return value,
+            Action::Remove(data) => return &data.values[self.merge.as_ref().unwrap().offset],
+            _ => unreachable!(),
+        }
+    }
+
+    /// Reverts the last action of history and moves its inverse in redo sequence.
+    /// Returns false if history is empty
+    pub(super) fn undo_step(&mut self) -> bool {
+        let Some(action) = self.history_pop() else {
+            return false;
+        };
+
+        let inverse = self.handle_action(action);
+        self.notify_done(&inverse);
+        self.notify(ChangeEvent::Undone);
+        self.redo_history.push(inverse);
+
+        let id = self.ids.history.pop_back().unwrap();
+        self.ids.redo.push(id);
+
+        return true;
+    }
+
+    /// Reverts the last action of redo sequence and moves its inverse in history.
+    /// Returns false if redo sequence is empty
+    pub(super) fn redo_step(&mut self) -> bool {
+        let Some(action) = self.redo_history.pop() else {
+            return false;
+        };
+
+        let inverse = self.handle_action(action);
+        self.notify_done(&inverse);
+        self.notify(ChangeEvent::Redone);
+        self.history_push(inverse);
+
+        let id = self.ids.redo.pop().unwrap();
+        self.ids.history.push_back(id);
+
+        return true;
+    }
+
+    /// Puts the element at `index + order[k]` to `index + k`, selects follow their elements.
+    /// Returns the order which brings the block back
+    pub(super) fn reorder(&mut self, index: usize, order: &[usize]) -> Vec<usize> {
+        let mut elements: Vec<Option<T>> = self.data.drain(index..index + order.len()).map(Some).collect();
+
+        self.data.insert_many(index, order.iter().map(|&k| elements[k].take().unwrap()));
+
+        let inverse = invert_order(order);
+
+        self.remap_selects(|i| match i.checked_sub(index).and_then(|k| inverse.get(k)) {
+            Some(&k) => Some(index + k),
+            None => Some(i),
+        });
+
+        return inverse;
+    }
+
+    /// Reorders the block at `index` by `order` like `reorder` and creates an action in history sequence,
+    /// nothing is recorded if the order doesn't change
+    pub(super) fn reorder_historic(&mut self, index: usize, order: Vec<usize>) {
+        if order.iter().enumerate().all(|(k, &from)| k == from) {
+            return;
+        }
+
+        let inverse = self.reorder(index, &order);
+
+        self.push_action(Action::Permute(PermuteData { index, order: inverse }));
+    }
+
+    /// Reverts an action and returns its inverse.
+    /// Handling the inverse action brings the collection back to the state before the revert
+    pub(super) fn handle_action(&mut self, action: Action<T>) -> Action<T> {
+        match action {
+            Action::PushBack => {
                 let elem = self.data.pop_back().unwrap();
+                self.shift_selects_removed(&[self.data.len()]);
 
-                return vec![elem];
+                return Action::PopBack(elem);
             }
             Action::PopBack(element) => {
-                self.selects.clear();
-
                 self.data.push_back(element);
+
+                return Action::PushBack;
             }
             Action::PushFront => {
-                self.selects.clear();
-
                 let elem = self.data.pop_front().unwrap();
-                return vec![elem];
+                self.shift_selects_removed(&[0]);
+
+                return Action::PopFront(elem);
             }
             Action::PopFront(element) => {
-                self.selects.clear();
-
                 self.data.push_front(element);
+                self.shift_selects_inserted(&[0]);
+
+                return Action::PushFront;
             }
             Action::Insert(data) => {
-                self.selects.clear();
+                let range = data.index..data.index + data.amount;
+                let mut values: Vec<T> = self.data.drain(range.clone()).collect();
 
-                let mut res: Vec<T> = Vec::with_capacity(data.amount);
+                // indecies are descending like remove_selects_historic makes them
+                values.reverse();
 
-                for _ in 0..data.amount {
-                    res.push(self.data.remove(data.index));
-                }
-                return res;
+                let removed: Vec<usize> = range.clone().collect();
+                self.shift_selects_removed(&removed);
+
+                return Action::Remove(RemoveData {
+                    indecies: range.rev().collect(),
+                    values,
+                });
             }
             Action::Remove(data) => {
-                self.selects.clear();
+                let RemoveData { indecies, values } = data;
 
-                // for (index, element) in data.into_iter().rev() {
-                //     self.data.insert(index, element);
-                //     self.selects.insert(index);
-                // }
+                let inserted: Vec<usize> = indecies.into_iter().rev().collect();
+                self.put_positions(&inserted, values.into_iter().rev());
 
-                let RemoveData { indecies, values } = data;
+                self.shift_selects_inserted(&inserted);
+                self.select_affected(inserted.iter().copied());
 
-                for (index, value) in indecies.into_iter().zip(values).rev() {
-                    self.data.insert(index, value);
-                    self.selects.insert(index);
-                }
+                return inserted_action(inserted);
+            }
+            Action::Reinsert(indecies) => {
+                let removed: Vec<usize> = indecies.iter().rev().copied().collect();
+                let mut values = self.take_positions(&removed);
+
+                values.reverse();
+                self.shift_selects_removed(&removed);
+
+                return Action::Remove(RemoveData { indecies, values });
             }
             Action::Move(data) => {
-                self.selects.clear();
+                let amount = data.indecies.len();
+                let elements: Vec<T> = self.data.drain(data.dest_index..data.dest_index + amount).collect();
 
-                let mut elements: VecDeque<T> = VecDeque::with_capacity(data.indecies.len());
+                self.put_positions(&data.indecies, elements);
 
-                for _ in 0..data.indecies.len() {
-                    let elem = self.data.remove(data.dest_index);
-                    elements.push_back(elem);
+                let moved: Vec<usize> = (data.dest_index..data.dest_index + data.indecies.len()).collect();
+                self.shift_selects_moved(&moved, &data.indecies);
+                self.select_affected(data.indecies.iter().copied());
+
+                return Action::MoveBack(data);
+            }
+            Action::MoveBack(data) => {
+                let elements = self.take_positions(&data.indecies);
+
+                let moved: Vec<usize> = (data.dest_index..data.dest_index + elements.len()).collect();
+                self.shift_selects_moved(&data.indecies, &moved);
+                self.select_affected(moved);
+
+                self.data.insert_many(data.dest_index, elements);
+
+                return Action::Move(data);
+            }
+            Action::Group(actions) => {
+                let mut inverses: Vec<Action<T>> = Vec::with_capacity(actions.len());
+
+                for action in actions.into_iter().rev() {
+                    inverses.push(self.handle_action(action));
                 }
 
-                for index in data.indecies.iter() {
-                    let index = index.clone();
+                return Action::Group(inverses);
+            }
+            Action::Custom(mut command) => {
+                command.revert(&mut StorageElements(&mut self.data));
+
+                return Action::CustomBack(command);
+            }
+            Action::CustomBack(mut command) => {
+                command.apply(&mut StorageElements(&mut self.data));
+
+                return Action::Custom(command);
+            }
+            Action::Permute(data) => {
+                let PermuteData { index, order } = data;
+                let inverse = self.reorder(index, &order);
+
+                self.select_affected((0..order.len()).filter(|&k| order[k] != k).map(|k| index + k));
+
+                return Action::Permute(PermuteData { index, order: inverse });
+            }
+            Action::Replace(data) => {
+                let ReplaceData { index, values } = data;
+                let mut replaced: Vec<T> = Vec::with_capacity(values.len());
 
-                    let elem = elements.pop_front().unwrap();
-                    self.data.insert(index, elem);
-                    self.selects.insert(index);
+                for (i, value) in values.into_iter().enumerate() {
+                    replaced.push(std::mem::replace(&mut self.data[index + i], value));
                 }
+
+                self.select_affected(index..index + replaced.len());
+
+                return Action::Replace(ReplaceData {
+                    index,
+                    values: replaced,
+                });
             }
         }
-
-        return vec![];
     }
 }
@@ -1,12 +1,17 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
 use std::ops::RangeBounds;
+use std::panic::{self, AssertUnwindSafe};
 
-use gapbuf::{Drain, GapBuffer};
-
-use super::defines::{Action, InsertData, MoveData, RemoveData};
+use super::defines::{
+    Action, ActionMeta, ChangeEvent, Drain, Edit, HistoricDrain, HistoricMut, HistoryBudget, HistoryEntry, HistoryLimits, InsertData, MergePolicy, MergeState, NodeId, ObserverId, RemoveData,
+    ReplaceData,
+};
 use super::private::*;
+use super::storage::Storage;
 use super::vec_historic::VecHistoric;
 
-impl<T> VecHistoric<T> {
+impl<T, S: Storage<T>> VecHistoric<T, S> {
     // #[inline(always)]
     // pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<T, I::IntoIter>
     // where
@@ -18,77 +23,90 @@ impl<T> VecHistoric<T> {
 
     /// Removes the last element from a vector and returns it, or [`None`] if it
     /// is empty.
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     #[inline(always)]
     pub fn pop_back(&mut self) -> Option<T> {
-        self.clear_history(); // to avoid indexs shifting
-        self.deselect_all(); // to avoid indexs shifting
-        self.data.pop_back()
+        let len = self.data.len();
+        let value = self.data.pop_back()?;
+        self.untracked_edit(Edit::Remove(vec![len - 1]), len); // to avoid indexs shifting
+        return Some(value);
     }
 
     /// Removes the last element from a vector and returns it, or [`None`] if it
     /// is empty.
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     #[inline(always)]
     pub fn pop_front(&mut self) -> Option<T> {
-        self.clear_history(); // to avoid indexs shifting
-        self.deselect_all(); // to avoid indexs shifting
-        self.data.pop_front()
+        let len = self.data.len();
+        let value = self.data.pop_front()?;
+        self.untracked_edit(Edit::Remove(vec![0]), len); // to avoid indexs shifting
+        return Some(value);
     }
 
     /// Appends an element to the back of a VecHistoric.
+    /// History is rebased in rebase mode
     ///
     /// # Panics
     /// Panics if the number of elements in the VecHistoric overflows a usize.
     #[inline(always)]
     pub fn push_back(&mut self, value: T) {
+        let len = self.data.len();
         self.data.push_back(value);
+
+        match self.rebase {
+            true => self.untracked_edit(Edit::Insert { index: len, amount: 1 }, len),
+            false => {
+                self.renew_current_state();
+                self.notify(ChangeEvent::Inserted(len..len + 1));
+
+                #[cfg(feature = "journal")]
+                self.journal_push_back();
+            }
+        }
     }
 
     /// Appends an element to the front of a VecHistoric.
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     ///
     /// # Panics
     /// Panics if the number of elements in the VecHistoric overflows a usize.
     #[inline(always)]
     pub fn push_front(&mut self, value: T) {
-        self.clear_history(); // to avoid indexs shifting
-        self.deselect_all(); // to avoid indexs shifting
+        let len = self.data.len();
         self.data.push_front(value);
+        self.untracked_edit(Edit::Insert { index: 0, amount: 1 }, len); // to avoid indexs shifting
     }
 
     /// Inserts an element at position `index` within the vector
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     ///
     /// # Panics
     ///
     /// Panics if `index > len`.
     #[inline(always)]
     pub fn insert(&mut self, index: usize, value: T) {
-        if index > self.data.len() {
-
-        }
-
-        self.clear_history(); // to avoid indexs shifting
-        self.deselect_all(); // to avoid indexs shifting
+        let len = self.data.len();
         self.data.insert(index, value);
+        self.untracked_edit(Edit::Insert { index, amount: 1 }, len); // to avoid indexs shifting
     }
 
     /// Inserts elements or iterator at position `index` within the vector
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     ///
     /// # Panics
     ///
     /// Panics if `index > len`.
     #[inline(always)]
     pub fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
-        self.clear_history(); // to avoid indexs shifting
-        self.deselect_all(); // to avoid indexs shifting
+        let len = self.data.len();
         self.data.insert_many(index, iter);
+
+        let amount = self.data.len() - len;
+        self.untracked_edit(Edit::Insert { index, amount }, len); // to avoid indexs shifting
     }
 
     /// Removes an element from the VecHistoric and returns it.
-    /// History and selects are wiped for preventing index shifting
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     ///
     /// # Panics
     /// Panics if `index >= self.len()`.
@@ -97,9 +115,10 @@ impl<T> VecHistoric<T> {
     /// `O(n)`, `n = |index - self.gap()|`
     #[inline(always)]
     pub fn remove(&mut self, index: usize) -> T {
-        self.clear_history();
-        self.deselect_all();
-        self.data.remove(index)
+        let len = self.data.len();
+        let value = self.data.remove(index);
+        self.untracked_edit(Edit::Remove(vec![index]), len);
+        return value;
     }
 
     /// Returns the number of elements in the VecHistoric.
@@ -114,75 +133,364 @@ impl<T> VecHistoric<T> {
         self.data.is_empty()
     }
 
-    /// Clears the GapBuffer, removing all values.
+    /// Clears the collection, removing all values.
     /// History and selects are wiped for preventing index shifting
     ///
-    /// Note that this method has no effect on the allocated capacity of the GapBuffer.
+    /// Note that this method has no effect on the allocated capacity of the storage.
     #[inline(always)]
     pub fn clear(&mut self) {
-        self.clear_history();
-        self.deselect_all();
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        self.wipe_history();
+        self.drop_selects();
+
+        let len = self.data.len();
         self.data.clear();
+
+        if len > 0 {
+            self.notify(ChangeEvent::Removed(0..len));
+        }
+
+        self.notify_selection();
     }
 
-    /// Creates a draining iterator that removes the specified range in the GapBuffer and yields the removed items.
-    /// History and selects are wiped for preventing index shifting
+    /// Creates a draining iterator that removes the specified range in the storage and yields the removed items.
+    /// Selects follow their elements, history is wiped for preventing index shifting or rebased in rebase mode
     ///
     /// - Note 1: The element range is removed even if the iterator is only partially consumed or not consumed at all.
-    /// - Note 2: It is unspecified how many elements are removed from the GapBuffer if the Drain value is leaked.
+    /// - Note 2: The range is removed before the iterator is returned, leaking it leaves the collection consistent.
     ///
     /// # Panics
     /// Panics if the `range` is out of bounds.
     #[inline(always)]
-    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, T> {
-        self.clear_history();
-        self.deselect_all();
-        self.data.drain(range)
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, T, S> {
+        let len = self.data.len();
+        let range = resolve_range(range, len);
+
+        let values: Vec<T> = self.data.drain(range.clone()).collect();
+        self.untracked_edit(Edit::Remove(range.collect()), len);
+
+        return Drain {
+            iter: values.into_iter(),
+            marker: PhantomData,
+        };
     }
 
-    /// Returns inner gap_buffer.
+    /// Returns inner storage.
     #[inline(always)]
-    pub fn get_inner_data(&self) -> &GapBuffer<T> {
+    pub fn get_inner_data(&self) -> &S {
         return &self.data;
     }
 
-    /// Returns inner gap_buffer.
+    /// Subscribes `callback` to changes of the collection and returns the id of the subscription.
+    /// Events are sent by historic and untracked methods, undo and redo, changes made through
+    /// `get_inner_data_mut` or `IndexMut` aren't reported
+    pub fn on_change(&mut self, callback: impl FnMut(&ChangeEvent) + Send + Sync + 'static) -> ObserverId {
+        let id = self.observers.next;
+        self.observers.next += 1;
+        self.observers.callbacks.push((id, Box::new(callback)));
+
+        return id;
+    }
+
+    /// Unsubscribes an observer, returns false if it doesn't exist
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        let len = self.observers.callbacks.len();
+        self.observers.callbacks.retain(|(observer, _)| *observer != id);
+
+        return self.observers.callbacks.len() < len;
+    }
+
+    /// Returns inner storage.
     #[inline(always)]
-    pub fn get_inner_data_mut(&mut self) -> &mut GapBuffer<T> {
+    pub fn get_inner_data_mut(&mut self) -> &mut S {
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
         return &mut self.data;
     }
 }
 
-impl<T> VecHistoric<T> {
-    /// Undo last action in the collection and returns erased elements of it
-    /// If history len is 0 OR an action contains no elements returns empty vec 
-    pub fn undo(&mut self) -> Vec<T> {
-        // returns erased elements
-        if self.history.len() <= 0 {
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Undo last action in the collection and returns the undone entry with its metadata,
+    /// `values` of the entry are addresses of erased elements of it
+    /// The undone action is pushed in redo sequence, erased elements are kept there
+    /// Selects follow their elements, elements put back, moved or replaced by the undo are selected
+    /// If history len is 0 OR a group is open returns None
+    pub fn undo(&mut self) -> Option<HistoryEntry<'_, T>> {
+        if self.history.is_empty() || !self.groups.is_empty() {
+            return None;
+        }
+
+        self.undo_step();
+
+        #[cfg(feature = "journal")]
+        self.journal_step(true);
+
+        return Some(self.redo_entry(self.redo_history.len() - 1));
+    }
+
+    /// Redo last undone action in the collection and returns the redone entry with its metadata,
+    /// `values` of the entry are addresses of erased elements of it
+    /// The redone action is pushed back in history sequence
+    /// Selects follow their elements, elements put back, moved or replaced by the redo are selected
+    /// If redo len is 0 OR a group is open returns None
+    pub fn redo(&mut self) -> Option<HistoryEntry<'_, T>> {
+        if self.redo_history.is_empty() || !self.groups.is_empty() {
+            return None;
+        }
+
+        self.redo_step();
+        self.enforce_history_limits();
+
+        #[cfg(feature = "journal")]
+        self.journal_step(false);
+
+        return Some(self.history_entry(self.history.len() - 1));
+    }
+
+    /// Opens a group of actions.
+    /// All actions made until the group is committed are undone and redone as one step.
+    /// Groups can be nested, the inner group becomes a part of the outer one
+    #[inline(always)]
+    pub fn begin_group(&mut self) {
+        self.groups.push(Vec::new());
+    }
+
+    /// Commits the innermost open group and pushes it in history sequence as a single action.
+    /// Returns false if there is no open group
+    pub fn commit_group(&mut self) -> bool {
+        let Some(mut actions) = self.groups.pop() else {
+            return false;
+        };
+
+        if let Some(outer) = self.groups.last_mut() {
+            outer.append(&mut actions);
+            return true;
+        }
+
+        match actions.len() {
+            0 => self.meta.pending = None,
+            1 => {
+                // a group of one action is still a step of its own, the merge policy doesn't join it with others
+                self.break_merge();
+                self.store_action(actions.pop().unwrap());
+                self.break_merge();
+            }
+            _ => self.store_action(Action::Group(actions)),
+        }
+
+        return true;
+    }
+
+    /// Rolls back all actions of the innermost open group and closes it.
+    /// Returns all elements erased by the rollback
+    pub fn abort_group(&mut self) -> Vec<T> {
+        let Some(actions) = self.groups.pop() else {
             return vec![];
+        };
+
+        let inverse = self.handle_action(Action::Group(actions));
+        self.notify_done(&inverse);
+
+        if self.groups.is_empty() {
+            self.meta.pending = None;
         }
 
-        let action = self.history.pop_back().unwrap();
-        self.deselect_all();
-        return self.handle_action(action);
+        return take_values_from_action(inverse);
+    }
+
+    /// Returns the count of open groups
+    #[inline(always)]
+    pub fn len_groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Runs `f` inside a group of actions which is undone as one step.
+    /// The group is rolled back if `f` returns an error or panics, the panic is resumed afterwards
+    pub fn transaction<R, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<R, E>) -> Result<R, E> {
+        self.begin_group();
+        let depth = self.groups.len();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+
+        let commit = matches!(result, Ok(Ok(_)));
+
+        // groups left open by `f` are closed together with the transaction
+        while self.groups.len() >= depth {
+            if commit {
+                self.commit_group();
+            } else {
+                self.abort_group();
+            }
+        }
+
+        match result {
+            Ok(result) => return result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
     }
 
-    /// Clears the history and returns all elements of all erased actions
+    /// Clears the history and redo sequences and returns all elements of all erased actions
+    /// Actions of open groups are erased too, the groups stay open
+    /// In undo tree mode the tree is compacted to the current node, ids of the nodes are kept
     pub fn clear_history(&mut self) -> Vec<T> {
-        let mut values: Vec<T> = Vec::with_capacity(self.compute_history_values_len());
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        return self.erase_history();
+    }
+
+    /// Sets limits of history sequence and returns elements of the actions evicted to fit them
+    pub fn set_history_limits(&mut self, limits: HistoryLimits<T>) -> Vec<T> {
+        let evicted = self.take_evicted();
+
+        self.budget = Some(HistoryBudget {
+            limits,
+            values: 0,
+            bytes: 0,
+            evicted,
+        });
+
+        self.recount_budget();
 
-        for action in self.history.drain(..) {
-            let taken_values = take_values_from_action(action);
-            values.extend(taken_values);
+        return self.evict_history(0);
+    }
+
+    /// Removes limits of history sequence and returns elements kept for `take_evicted`
+    #[inline(always)]
+    pub fn remove_history_limits(&mut self) -> Vec<T> {
+        let evicted = self.take_evicted();
+        self.budget = None;
+        return evicted;
+    }
+
+    /// Returns limits of history sequence if set
+    #[inline(always)]
+    pub fn history_limits(&self) -> Option<&HistoryLimits<T>> {
+        self.budget.as_ref().map(|budget| &budget.limits)
+    }
+
+    /// Takes elements of the actions evicted by history limits with `keep_evicted` set
+    #[inline(always)]
+    pub fn take_evicted(&mut self) -> Vec<T> {
+        match self.budget.as_mut() {
+            Some(budget) => std::mem::take(&mut budget.evicted),
+            None => vec![],
         }
+    }
+
+    /// Sets the policy of merging adjacent insertions or removals into one history entry.
+    /// Changing the policy doesn't merge anything into entries made before it
+    pub fn set_merge_policy(&mut self, policy: MergePolicy) {
+        self.merge = Some(MergeState {
+            policy,
+            last: None,
+            offset: 0,
+        });
+    }
+
+    /// Removes the merge policy, every action makes its own history entry
+    #[inline(always)]
+    pub fn remove_merge_policy(&mut self) {
+        self.merge = None;
+    }
+
+    /// Returns the merge policy if set
+    #[inline(always)]
+    pub fn merge_policy(&self) -> Option<&MergePolicy> {
+        self.merge.as_ref().map(|merge| &merge.policy)
+    }
 
-        return values;
+    /// Names the current state as a checkpoint and returns its node.
+    /// A checkpoint with the same name is moved to the current state
+    pub fn checkpoint(&mut self, name: impl Into<String>) -> NodeId {
+        let id = self.ids.current();
+        self.checkpoints.insert(name.into(), id);
+        return id;
+    }
+
+    /// Removes a checkpoint, returns false if it doesn't exist
+    #[inline(always)]
+    pub fn remove_checkpoint(&mut self, name: &str) -> bool {
+        self.checkpoints.shift_remove(name).is_some()
+    }
+
+    /// Returns true if the checkpoint exists and its state can still be reached by undo or redo
+    /// A checkpoint is invalidated when its state is dropped from history:
+    /// by `clear_history`, an untracked mutation, history limits or a new action after undo in linear mode
+    pub fn is_checkpoint_valid(&self, name: &str) -> bool {
+        match self.checkpoints.get(name) {
+            Some(&id) => self.is_state_reachable(id),
+            None => false,
+        }
+    }
+
+    /// Returns names of the checkpoints which were invalidated
+    pub fn invalid_checkpoints(&self) -> Vec<&str> {
+        let invalid: Vec<&str> = self
+            .checkpoints
+            .iter()
+            .filter(|&(_, &id)| !self.is_state_reachable(id))
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        return invalid;
+    }
+
+    /// Brings the collection to the state of a checkpoint undoing (or redoing) actions in one call
+    /// Returns false if the checkpoint doesn't exist, was invalidated or a group is open
+    pub fn undo_to(&mut self, name: &str) -> bool {
+        let Some(&id) = self.checkpoints.get(name) else {
+            return false;
+        };
+
+        if !self.groups.is_empty() {
+            return false;
+        }
+
+        if self.tree.is_some() {
+            return self.jump_to_node(id);
+        }
+
+        if id == self.ids.root || self.ids.history.contains(&id) {
+            while self.ids.current() != id {
+                self.undo_step();
+            }
+        } else if self.ids.redo.contains(&id) {
+            while self.ids.current() != id {
+                self.redo_step();
+            }
+            self.enforce_history_limits();
+        } else {
+            return false;
+        }
+
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        return true;
+    }
+
+    /// Marks the current state as saved
+    #[inline(always)]
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.ids.current());
+    }
+
+    /// Returns true if the collection was changed since the last save point
+    /// A new collection is not dirty until it's changed
+    pub fn is_dirty(&self) -> bool {
+        let grouped = self.groups.iter().any(|group| !group.is_empty());
+
+        return grouped || self.saved != Some(self.ids.current());
     }
 
     /// Clears selects
     #[inline(always)]
     pub fn clear_selects(&mut self) {
-        self.selects.clear();
+        self.deselect_all();
     }
 
     /// Returns the count of selected elements
@@ -197,16 +505,36 @@ impl<T> VecHistoric<T> {
         self.history.len()
     }
 
+    /// Returns the count of undone actions which can be redone
+    #[inline(always)]
+    pub fn len_redo(&self) -> usize {
+        self.redo_history.len()
+    }
+
     /// Returns the iterator of selections
     #[inline(always)]
     pub fn iter_selects(&self) -> indexmap::set::Iter<'_, usize> {
         self.selects.iter()
     }
 
-    /// Returns the iterator of history
+    /// Returns the iterator of history entries with their metadata, the next entry to undo is the last one
     #[inline(always)]
-    pub fn iter_history(&self) -> std::collections::vec_deque::Iter<'_, Action<T>> {
-        self.history.iter()
+    pub fn iter_history(&self) -> impl DoubleEndedIterator<Item = HistoryEntry<'_, T>> + ExactSizeIterator {
+        (0..self.history.len()).map(|index| self.history_entry(index))
+    }
+
+    /// Returns the iterator of redo sequence entries with their metadata, the next entry to redo is the last one
+    #[inline(always)]
+    pub fn iter_redo(&self) -> impl DoubleEndedIterator<Item = HistoryEntry<'_, T>> + ExactSizeIterator {
+        (0..self.redo_history.len()).map(|index| self.redo_entry(index))
+    }
+
+    /// Attaches `meta` to the next entry of history sequence, e.g. `b.with_meta(meta).push_back_historic(1)`.
+    /// Inside a group it's attached to the group, a command merged into the previous one replaces its metadata
+    #[inline(always)]
+    pub fn with_meta(&mut self, meta: ActionMeta) -> &mut Self {
+        self.meta.pending = Some(meta);
+        return self;
     }
 
     /// Returns the values of selected elements
@@ -220,13 +548,20 @@ impl<T> VecHistoric<T> {
     /// Selects an element by index
     #[inline(always)]
     pub fn select(&mut self, index: usize) {
-        self.selects.insert(index);
+        self.select_affected([index]);
+        self.notify_selection();
     }
 
     /// Deselects an element by index
     #[inline(always)]
     pub fn deselect(&mut self, index: usize) -> bool {
-        self.selects.shift_remove(&index)
+        let deselected = self.selects.shift_remove(&index);
+
+        if deselected {
+            self.notify(ChangeEvent::SelectionChanged);
+        }
+
+        return deselected;
     }
 
     /// Returns true if an element is selected
@@ -238,53 +573,39 @@ impl<T> VecHistoric<T> {
     /// Deselect all elements
     #[inline(always)]
     pub fn deselect_all(&mut self) {
-        self.selects.clear();
+        self.drop_selects();
+        self.notify_selection();
     }
 
     /// Selects all elements
     #[inline(always)]
     pub fn select_all(&mut self) {
-        for i in 0..self.data.len() {
-            self.selects.insert(i);
-        }
+        self.select_affected(0..self.data.len());
+        self.notify_selection();
     }
 
     /// Removes the last element from a VecHistoric and returns its address, or [`None`] if it
     /// is empty.
     /// Creates an action in history sequence
     pub fn pop_back_historic(&mut self) -> Option<&T> {
-        self.deselect_all(); // to avoid index shifting
-
         let element = self.data.pop_back()?;
+        self.shift_selects_removed(&[self.data.len()]);
 
-        self.history.push_back(Action::PopBack(element));
-
-        let action = self.history.iter().last().unwrap();
+        self.push_action(Action::PopBack(element));
 
-        let Action::PopBack(value) = action else {
-            unreachable!()
-        };
-
-        return Some(value);
+        return Some(self.last_pushed_value());
     }
 
     /// Removes the first element from a VecHistoric and returns its address, or [`None`] if it
     /// is empty.
     /// Creates an action in history sequence
     pub fn pop_front_historic(&mut self) -> Option<&T> {
-        self.deselect_all(); // to avoid index shifting
-
         let element = self.data.pop_front()?;
+        self.shift_selects_removed(&[0]);
 
-        self.history.push_back(Action::PopFront(element));
-
-        let action = self.history.iter().last().unwrap();
-
-        let Action::PopFront(value) = action else {
-            unreachable!()
-        };
+        self.push_action(Action::PopFront(element));
 
-        return Some(value);
+        return Some(self.last_pushed_value());
     }
 
     /// Appends an element to the back of a VecHistoric.
@@ -296,7 +617,7 @@ impl<T> VecHistoric<T> {
     pub fn push_back_historic(&mut self, value: T) {
         self.data.push_back(value);
 
-        self.history.push_back(Action::PushBack);
+        self.push_action(Action::PushBack);
     }
 
     /// Appends an element to the front of a VecHistoric.
@@ -306,96 +627,89 @@ impl<T> VecHistoric<T> {
     /// Panics if the number of elements in the VecHistoric overflows a usize.
     #[inline(always)]
     pub fn push_front_historic(&mut self, value: T) {
-        self.deselect_all(); // to avoid index shifting
-
         self.data.push_front(value);
+        self.shift_selects_inserted(&[0]);
 
-        self.history.push_back(Action::PushFront);
+        self.push_action(Action::PushFront);
     }
 
     /// Inserts an element at position `index` within the VecHistoric
-    /// Selects the inserted element
+    /// Selects the inserted element, the existing selects follow their elements
     /// Creates an action in history sequence
     ///
     /// # Panics
     ///
     /// Panics if `index > len`.
     pub fn insert_historic(&mut self, index: usize, value: T) {
-        self.deselect_all(); // to avoid index shifting
-
         self.data.insert(index, value);
+        self.shift_selects_inserted(&[index]);
 
         let insert_data = InsertData {
-            index: index,
+            index,
             amount: 1,
         };
 
-        self.select(index);
+        self.select_affected([index]);
 
-        self.history.push_back(Action::Insert(insert_data));
+        self.push_action(Action::Insert(insert_data));
     }
 
     /// Inserts an elements or iterator at position `index` within the VecHistoric
-    /// Selects the inserted elements
+    /// Selects the inserted elements, the existing selects follow their elements
     /// Creates an action in history sequence
     ///
     /// # Panics
     ///
     /// Panics if `index > len`.
     pub fn insert_many_historic(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
-        self.deselect_all(); // to avoid index shifting
-
         let items: Vec<T> = iter.into_iter().collect();
         let amount = items.len();
 
         self.data.insert_many(index, items);
 
-        let insert_data = InsertData { index, amount };
+        let inserted: Vec<usize> = (index..index + amount).collect();
+        self.shift_selects_inserted(&inserted);
+        self.select_affected(inserted);
 
-        for i in 0..amount {
-            self.select(index + i);
-        }
+        let insert_data = InsertData { index, amount };
 
-        self.history.push_back(Action::Insert(insert_data));
+        self.push_action(Action::Insert(insert_data));
     }
 
-    /// Removes selected elements and returns them
+    /// Removes selected elements and returns them.
+    /// History is wiped for preventing index shifting or rebased in rebase mode
     pub fn remove_selects(&mut self) -> Vec<T> {
+        let len = self.data.len();
         let selects = self.get_selects_sorted();
 
-        let mut elems: Vec<T> = Vec::with_capacity(selects.len());
-
-        for aselect in selects.iter().rev() {
-            elems.push(self.data.remove(aselect.clone()));
-        }
+        // elements are returned from the back like they were removed one by one
+        let mut elems = self.take_positions(&selects);
+        elems.reverse();
 
-        self.selects.clear();
+        // selects of the removed elements are dropped with them
+        self.untracked_edit(Edit::Remove(selects), len);
 
         return elems;
     }
 
-    /// Removes selected elements and returns address of the removed elements
+    /// Removes selected elements and returns address of the removed elements,
+    /// all elements of the entry are returned if the removal was merged into it by the merge policy
     /// Creates an action in history sequence
     pub fn remove_selects_historic(&mut self) -> &Vec<T> {
         let selects = self.get_selects_sorted();
 
-        // let mut remove_data: RemoveData<T> = Vec::with_capacity(self.data.len());
+        let mut values = self.take_positions(&selects);
+        values.reverse();
 
-        let mut remove_data = RemoveData::new(self.data.len());
-
-        for sel in selects.iter().rev() {
-            let sel = sel.clone();
-            let elem = self.data.remove(sel);
-
-            // remove_data.push((sel, elem));
-            remove_data.indecies.push(sel);
-            remove_data.values.push(elem);
-        }
+        let remove_data = RemoveData {
+            indecies: selects.into_iter().rev().collect(),
+            values,
+        };
 
-        self.history.push_back(Action::Remove(remove_data));
-        self.deselect_all();
+        self.drop_selects();
+        self.push_action(Action::Remove(remove_data));
 
-        let action = self.history.iter().last().unwrap();
+        let action = self.last_pushed_action();
 
         let Action::Remove(remove_data) = action else {
             unreachable!()
@@ -404,51 +718,330 @@ impl<T> VecHistoric<T> {
         return &remove_data.values;
     }
 
-    /// Moves selected elements to a specific position `index`
-    /// History and selects are wiped for preventing index shifting
-    pub fn move_selects(&mut self, to_index: usize) {
-        let selects = self.get_selects_sorted();
+    /// Removes the `range` and returns the removed elements from the last one, like `remove_selects_historic`.
+    /// Selects of the removed elements are dropped, other selects follow their elements
+    /// Creates an action in history sequence, it isn't merged by the merge policy. Nothing is recorded for an empty range
+    ///
+    /// # Panics
+    /// Panics if the `range` is out of bounds.
+    pub fn drain_historic(&mut self, range: impl RangeBounds<usize>) -> &[T] {
+        let range = resolve_range(range, self.data.len());
+
+        if range.is_empty() {
+            return &[];
+        }
 
-        let mut selected_elements: Vec<T> = Vec::with_capacity(selects.len());
+        let action = self.take_range(range);
 
-        let mut to_index = to_index;
+        self.break_merge();
+        self.push_action(action);
 
-        for aselect in selects.iter().rev() {
-            let item = self.data.remove(aselect.clone());
-            selected_elements.push(item);
+        return self.last_removed_range();
+    }
+
+    /// Replaces the `range` by elements of the iterator and returns the removed elements from the last one.
+    /// Selects the inserted elements, selects of the removed elements are dropped
+    /// Creates an action in history sequence, it isn't merged by the merge policy.
+    /// Nothing is recorded for an empty range and an empty iterator
+    ///
+    /// # Panics
+    /// Panics if the `range` is out of bounds.
+    pub fn splice_historic(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        iter: impl IntoIterator<Item = T>,
+    ) -> &[T] {
+        let range = resolve_range(range, self.data.len());
+        let index = range.start;
+
+        let items: Vec<T> = iter.into_iter().collect();
+        let amount = items.len();
+
+        if range.is_empty() && amount == 0 {
+            return &[];
         }
 
-        selected_elements.reverse();
+        let removed = self.take_range(range);
+
+        self.data.insert_many(index, items);
+
+        let inserted: Vec<usize> = (index..index + amount).collect();
+        self.shift_selects_inserted(&inserted);
+        self.select_affected(inserted);
+
+        // the removal is reverted last
+        let insert = Action::Insert(InsertData { index, amount });
+
+        self.break_merge();
+        self.push_action(Action::Group(vec![removed, insert]));
+
+        return self.last_removed_range();
+    }
+
+    /// Creates a draining iterator over the `range` which yields clones of the elements.
+    /// The whole range is removed when the iterator is dropped and recorded as one action in history sequence,
+    /// it isn't merged by the merge policy. Nothing is removed if the iterator is leaked
+    ///
+    /// # Panics
+    /// Panics if the `range` is out of bounds.
+    pub fn drain_iter_historic(&mut self, range: impl RangeBounds<usize>) -> HistoricDrain<'_, T, S>
+    where
+        T: Clone,
+    {
+        let range = resolve_range(range, self.data.len());
+
+        return HistoricDrain {
+            front: range.start,
+            back: range.end,
+            range,
+            inner: self,
+        };
+    }
+
+    /// Shortens the collection to `len` elements and returns the removed elements from the last one.
+    /// Does nothing if `len` is greater or equal to the current length
+    /// Creates an action in history sequence, it isn't merged by the merge policy
+    #[inline(always)]
+    pub fn truncate_historic(&mut self, len: usize) -> &[T] {
+        let len = len.min(self.data.len());
+        return self.drain_historic(len..);
+    }
 
-        if to_index > self.data.len() {
-            to_index = self.data.len();
+    /// Removes elements starting from `at` and returns them from the last one
+    /// Creates an action in history sequence, it isn't merged by the merge policy
+    ///
+    /// # Panics
+    /// Panics if `at > len`.
+    #[inline(always)]
+    pub fn split_off_historic(&mut self, at: usize) -> &[T] {
+        let len = self.data.len();
+        assert!(at <= len, "`at` split index (is {at}) should be <= len (is {len})");
+
+        return self.drain_historic(at..);
+    }
+
+    /// Retains only the elements specified by the predicate, removing the others in one pass.
+    /// Selects of the removed elements are dropped, other selects follow their elements
+    /// Creates one action in history sequence, nothing is recorded if every element is retained
+    pub fn retain_historic(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.compact_historic(|value| !f(value));
+    }
+
+    /// Removes consecutive elements which resolve to the same key but the first of them, in one pass.
+    /// Selects of the removed elements are dropped, other selects follow their elements
+    /// Creates one action in history sequence, nothing is recorded if there are no duplicates
+    pub fn dedup_by_key_historic<K: PartialEq>(&mut self, mut key: impl FnMut(&T) -> K) {
+        let mut last: Option<K> = None;
+
+        self.compact_historic(|value| {
+            let next = key(value);
+            let duplicate = last.as_ref() == Some(&next);
+
+            last = Some(next);
+            return duplicate;
+        });
+    }
+
+    /// Removes the elements specified by the predicate in one pass and returns them from the last one.
+    /// Selects of the removed elements are dropped, other selects follow their elements
+    /// Creates one action in history sequence, nothing is recorded if no element is removed
+    pub fn extract_if_historic(&mut self, pred: impl FnMut(&T) -> bool) -> &[T] {
+        if !self.compact_historic(pred) {
+            return &[];
         }
 
-        self.selects.clear();
-        self.history.clear();
-        for i in 0..selected_elements.len() {
-            self.selects.insert(to_index + i);
+        return self.last_removed_range();
+    }
+
+    /// Moves selected elements before the element at position `to_index` counted before their removal,
+    /// `to_index >= len` moves them to the back. The moved elements keep their order and stay selected
+    /// History is wiped for preventing index shifting
+    pub fn move_selects(&mut self, to_index: usize) {
+        if self.selects.is_empty() {
+            return;
         }
-        self.data.insert_many(to_index, selected_elements);
+
+        let move_data = self.selects_move_data(to_index);
+
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        self.wipe_history();
+
+        let inverse = self.handle_action(Action::MoveBack(move_data));
+        self.notify_done(&inverse);
     }
 
-    /// Moves selected elements to a specific position `index`
+    /// Moves selected elements before the element at position `to_index` counted before their removal,
+    /// `to_index >= len` moves them to the back. The moved elements keep their order and stay selected
     /// Creates an action in history sequence
     pub fn move_selects_historic(&mut self, to_index: usize) {
-        let selects = self.get_selects_sorted();
+        if self.selects.is_empty() {
+            return;
+        }
+
+        let move_data = self.selects_move_data(to_index);
+
+        // reverting MoveBack makes the move
+        let action = self.handle_action(Action::MoveBack(move_data));
+
+        self.push_action(action);
+    }
+
+    /// Sorts the collection with a comparator, the sort is stable. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation, nothing is recorded if the order doesn't change
+    pub fn sort_by_historic(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        let mut order: Vec<usize> = (0..self.data.len()).collect();
+        order.sort_by(|&a, &b| compare(&self.data[a], &self.data[b]));
+
+        self.reorder_historic(0, order);
+    }
+
+    /// Sorts the collection with a key extraction function, the sort is stable. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation, nothing is recorded if the order doesn't change
+    pub fn sort_by_key_historic<K: Ord>(&mut self, mut f: impl FnMut(&T) -> K) {
+        let mut order: Vec<usize> = (0..self.data.len()).collect();
+        order.sort_by_key(|&i| f(&self.data[i]));
+
+        self.reorder_historic(0, order);
+    }
+
+    /// Sorts the collection, the sort is stable. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation, nothing is recorded if the order doesn't change
+    #[inline(always)]
+    pub fn sort_historic(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by_historic(T::cmp);
+    }
+
+    /// Reverses the order of elements. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation
+    pub fn reverse_historic(&mut self) {
+        let order: Vec<usize> = (0..self.data.len()).rev().collect();
+        self.reorder_historic(0, order);
+    }
+
+    /// Rotates the collection in-place such that the element at `mid` becomes the first. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation
+    ///
+    /// # Panics
+    /// Panics if `mid > len`.
+    pub fn rotate_left_historic(&mut self, mid: usize) {
+        let len = self.data.len();
+        assert!(mid <= len, "`mid` (is {mid}) should be <= len (is {len})");
+
+        let order: Vec<usize> = (mid..len).chain(0..mid).collect();
+        self.reorder_historic(0, order);
+    }
+
+    /// Rotates the collection in-place such that the last `k` elements become the first. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation
+    ///
+    /// # Panics
+    /// Panics if `k > len`.
+    pub fn rotate_right_historic(&mut self, k: usize) {
+        let len = self.data.len();
+        assert!(k <= len, "`k` (is {k}) should be <= len (is {len})");
+
+        self.rotate_left_historic(len - k);
+    }
+
+    /// Swaps two elements. Selects follow their elements
+    /// Creates an action in history sequence keeping the permutation of the elements between them
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds.
+    pub fn swap_historic(&mut self, a: usize, b: usize) {
+        let len = self.data.len();
+        assert!(a < len && b < len, "swap indecies (are {a} and {b}) should be < len (is {len})");
+
+        let (lo, hi) = (a.min(b), a.max(b));
+        let mut order: Vec<usize> = (0..=hi - lo).collect();
+        order.swap(0, hi - lo);
+
+        self.reorder_historic(lo, order);
+    }
+
+    /// Replaces an element at position `index` and returns address of the replaced element
+    /// Creates an action in history sequence
+    ///
+    /// # Panics
+    /// Panics if `index >= len`.
+    pub fn set_historic(&mut self, index: usize, value: T) -> &T {
+        let old = std::mem::replace(&mut self.data[index], value);
+
+        let replace_data = ReplaceData {
+            index,
+            values: vec![old],
+        };
+
+        self.push_action(Action::Replace(replace_data));
+
+        let action = self.last_pushed_action();
+
+        let Action::Replace(replace_data) = action else {
+            unreachable!()
+        };
+
+        return &replace_data.values[0];
+    }
 
-        let mut to_index = to_index;
+    /// Replaces elements in `range` by elements of the iterator and returns the replaced elements
+    /// Creates an action in history sequence. Nothing is recorded for an empty range
+    ///
+    /// # Panics
+    /// Panics if the `range` is out of bounds or the iterator yields other count of elements than `range` has.
+    pub fn replace_range_historic(&mut self, range: impl RangeBounds<usize>, iter: impl IntoIterator<Item = T>) -> &[T] {
+        let range = resolve_range(range, self.data.len());
+        let values: Vec<T> = iter.into_iter().collect();
 
-        if to_index >= self.data.len() {
-            to_index = self.data.len() - selects.len();
+        assert_eq!(values.len(), range.len(), "replace_range_historic: count of elements doesn't match the range");
+
+        if range.is_empty() {
+            return &[];
         }
 
-        let move_data = MoveData {
-            dest_index: to_index,
-            indecies: selects.clone(),
+        let mut replaced: Vec<T> = Vec::with_capacity(values.len());
+
+        for (i, value) in values.into_iter().enumerate() {
+            replaced.push(std::mem::replace(&mut self.data[range.start + i], value));
+        }
+
+        let replace_data = ReplaceData {
+            index: range.start,
+            values: replaced,
         };
 
-        self.history.push_back(Action::Move(move_data));
-        self.move_selects(to_index);
+        self.push_action(Action::Replace(replace_data));
+
+        let action = self.last_pushed_action();
+
+        let Action::Replace(replace_data) = action else {
+            unreachable!()
+        };
+
+        return &replace_data.values;
+    }
+
+    /// Returns mutable access to an element at position `index`
+    /// The element is cloned and an action is created in history sequence when the guard is dropped,
+    /// nothing is recorded if the element wasn't accessed mutably
+    ///
+    /// # Panics
+    /// Panics if `index >= len`.
+    pub fn get_mut_historic(&mut self, index: usize) -> HistoricMut<'_, T, S>
+    where
+        T: Clone,
+    {
+        let old = self.data[index].clone();
+
+        return HistoricMut {
+            inner: self,
+            index,
+            old: Some(old),
+            written: false,
+        };
     }
 }
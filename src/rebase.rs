@@ -0,0 +1,579 @@
+use std::mem;
+
+use super::defines::{Action, Edit, MoveData, NodeId, PermuteData, RemoveData, ReplaceData};
+use super::private::*;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+
+impl Edit {
+    /// Returns true if the edit doesn't change the collection
+    #[inline(always)]
+    pub(super) fn is_empty(&self) -> bool {
+        match self {
+            Edit::Insert { amount, .. } => *amount == 0,
+            Edit::Remove(indecies) => indecies.is_empty(),
+        }
+    }
+
+    /// Returns the change of the collection length made by the edit
+    #[inline(always)]
+    pub(super) fn len_delta(&self) -> isize {
+        match self {
+            Edit::Insert { amount, .. } => *amount as isize,
+            Edit::Remove(indecies) => -(indecies.len() as isize),
+        }
+    }
+}
+
+/// Shifts positions `inserted` (ascending) of elements put in by an action through `edit` made after it.
+/// Returns the shifted positions and the edit as if it was made before the action
+fn rebase_inserted(inserted: &[usize], edit: &Edit) -> (Vec<usize>, Edit) {
+    match edit {
+        Edit::Insert { index, amount } => {
+            let before = inserted.partition_point(|&i| i < *index);
+
+            let shifted = inserted
+                .iter()
+                .map(|&i| if i >= *index { i + amount } else { i })
+                .collect();
+
+            return (shifted, Edit::Insert { index: index - before, amount: *amount });
+        }
+        Edit::Remove(removed) => {
+            let mut shifted: Vec<usize> = Vec::with_capacity(inserted.len());
+            let mut pre_removed: Vec<usize> = Vec::with_capacity(removed.len());
+
+            // `a` and `b` are the counts of passed inserted and removed positions
+            let (mut a, mut b) = (0, 0);
+
+            while a < inserted.len() || b < removed.len() {
+                match (inserted.get(a), removed.get(b)) {
+                    // the element put in by the action is removed by the edit
+                    (Some(i), Some(r)) if i == r => {
+                        a += 1;
+                        b += 1;
+                    }
+                    (Some(&i), r) if r.is_none_or(|&r| i < r) => {
+                        shifted.push(i - b);
+                        a += 1;
+                    }
+                    (_, Some(&r)) => {
+                        pre_removed.push(r - a);
+                        b += 1;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+
+            return (shifted, Edit::Remove(pre_removed));
+        }
+    }
+}
+
+/// Shifts positions `removed` (ascending, before the action) of elements taken out by an action
+/// through `edit` made after it.
+/// Returns the shifted positions and the edit as if it was made before the action
+fn rebase_removed(removed: &[usize], edit: &Edit) -> (Vec<usize>, Edit) {
+    match edit {
+        Edit::Insert { index, amount } => {
+            let mut pre_index = *index;
+
+            for &r in removed {
+                if r > pre_index {
+                    break;
+                }
+                pre_index += 1;
+            }
+
+            let shifted = removed
+                .iter()
+                .map(|&r| if r >= pre_index { r + amount } else { r })
+                .collect();
+
+            return (shifted, Edit::Insert { index: pre_index, amount: *amount });
+        }
+        Edit::Remove(edit_removed) => {
+            let mut pre_removed: Vec<usize> = Vec::with_capacity(edit_removed.len());
+            let mut passed = 0;
+
+            for &d in edit_removed {
+                let mut q = d + passed;
+
+                while passed < removed.len() && removed[passed] <= q {
+                    passed += 1;
+                    q += 1;
+                }
+
+                pre_removed.push(q);
+            }
+
+            let shifted = removed
+                .iter()
+                .map(|&r| r - pre_removed.partition_point(|&d| d < r))
+                .collect();
+
+            return (shifted, Edit::Remove(pre_removed));
+        }
+    }
+}
+
+/// Transforms a stored action through an untracked `edit` of the state the action leads to,
+/// `post_len` is the length of that state.
+/// Returns the transformed action with the edit as if it was made before the action,
+/// or elements kept by the action if it depends on elements touched by the edit
+pub fn rebase_action<T>(action: Action<T>, edit: &Edit, post_len: usize) -> Result<(Action<T>, Edit), Vec<T>> {
+    if edit.is_empty() {
+        return Ok((action, edit.clone()));
+    }
+
+    let new_post_len = (post_len as isize + edit.len_delta()) as usize;
+
+    match action {
+        Action::Insert(data) => {
+            let inserted: Vec<usize> = (data.index..data.index + data.amount).collect();
+            let (shifted, pre_edit) = rebase_inserted(&inserted, edit);
+
+            return Ok((inserted_action(shifted), pre_edit));
+        }
+        Action::Reinsert(indecies) => {
+            let inserted: Vec<usize> = indecies.into_iter().rev().collect();
+            let (shifted, pre_edit) = rebase_inserted(&inserted, edit);
+
+            return Ok((Action::Reinsert(shifted.into_iter().rev().collect()), pre_edit));
+        }
+        Action::PushBack => {
+            let (shifted, pre_edit) = rebase_inserted(&[post_len - 1], edit);
+
+            if shifted.first().is_some_and(|&i| i + 1 == new_post_len) {
+                return Ok((Action::PushBack, pre_edit));
+            }
+
+            return Ok((inserted_action(shifted), pre_edit));
+        }
+        Action::PushFront => {
+            let (shifted, pre_edit) = rebase_inserted(&[0], edit);
+
+            if shifted == [0] {
+                return Ok((Action::PushFront, pre_edit));
+            }
+
+            return Ok((inserted_action(shifted), pre_edit));
+        }
+        Action::Remove(data) => {
+            // indecies are descending, values are aligned with them
+            let removed: Vec<usize> = data.indecies.iter().rev().copied().collect();
+            let (shifted, pre_edit) = rebase_removed(&removed, edit);
+
+            let data = RemoveData {
+                indecies: shifted.into_iter().rev().collect(),
+                values: data.values,
+            };
+
+            return Ok((Action::Remove(data), pre_edit));
+        }
+        Action::PopBack(value) => {
+            let (shifted, pre_edit) = rebase_removed(&[post_len], edit);
+
+            if shifted[0] == new_post_len {
+                return Ok((Action::PopBack(value), pre_edit));
+            }
+
+            let data = RemoveData {
+                indecies: shifted,
+                values: vec![value],
+            };
+
+            return Ok((Action::Remove(data), pre_edit));
+        }
+        Action::PopFront(value) => {
+            let (shifted, pre_edit) = rebase_removed(&[0], edit);
+
+            if shifted[0] == 0 {
+                return Ok((Action::PopFront(value), pre_edit));
+            }
+
+            let data = RemoveData {
+                indecies: shifted,
+                values: vec![value],
+            };
+
+            return Ok((Action::Remove(data), pre_edit));
+        }
+        Action::Replace(data) => {
+            // positions out of the replaced range are the same before and after the action
+            return Ok((rebase_replace(data, edit), edit.clone()));
+        }
+        Action::Move(data) => {
+            let data = rebase_move(data, edit).ok_or_else(Vec::new)?;
+            return Ok((Action::Move(data), edit.clone()));
+        }
+        Action::MoveBack(data) => {
+            let data = rebase_move(data, edit).ok_or_else(Vec::new)?;
+            return Ok((Action::MoveBack(data), edit.clone()));
+        }
+        Action::Permute(data) => {
+            let data = rebase_permute(data, edit).ok_or_else(Vec::new)?;
+            return Ok((Action::Permute(data), edit.clone()));
+        }
+        // positions kept by a command are unknown
+        action @ (Action::Custom(_) | Action::CustomBack(_)) => return Err(take_values_from_action(action)),
+        Action::Group(mut actions) => {
+            let mut rebased: Vec<Action<T>> = Vec::with_capacity(actions.len());
+            let mut edit = edit.clone();
+            let mut post_len = post_len;
+
+            // the last action of a group is reverted first
+            while let Some(action) = actions.pop() {
+                let delta = action_len_delta(&action);
+
+                let (action, pre_edit) = match rebase_action(action, &edit, post_len) {
+                    Ok(rebased) => rebased,
+                    Err(values) => {
+                        // elements of the whole group are dropped with it
+                        let mut dropped: Vec<T> = actions.into_iter().flat_map(take_values_from_action).collect();
+                        dropped.extend(values);
+                        dropped.extend(rebased.into_iter().rev().flat_map(take_values_from_action));
+                        return Err(dropped);
+                    }
+                };
+
+                rebased.push(action);
+                edit = pre_edit;
+                post_len = (post_len as isize - delta) as usize;
+            }
+
+            rebased.reverse();
+
+            return Ok((Action::Group(rebased), edit));
+        }
+    }
+}
+
+/// Shifts replaced elements through `edit`, elements removed by the edit are forgotten
+fn rebase_replace<T>(mut data: ReplaceData<T>, edit: &Edit) -> Action<T> {
+    match edit {
+        Edit::Insert { index, amount } => {
+            let end = data.index + data.values.len();
+
+            if *index <= data.index {
+                data.index += amount;
+            } else if *index < end {
+                // the range is split by inserted elements
+                let tail = ReplaceData {
+                    index: index + amount,
+                    values: data.values.split_off(index - data.index),
+                };
+
+                return Action::Group(vec![Action::Replace(data), Action::Replace(tail)]);
+            }
+
+            return Action::Replace(data);
+        }
+        Edit::Remove(removed) => {
+            let start = data.index;
+            let mut values: Vec<T> = Vec::with_capacity(data.values.len());
+
+            for (i, value) in data.values.into_iter().enumerate() {
+                if removed.binary_search(&(start + i)).is_err() {
+                    values.push(value);
+                }
+            }
+
+            let index = start - removed.partition_point(|&r| r < start);
+
+            return Action::Replace(ReplaceData { index, values });
+        }
+    }
+}
+
+/// Shifts a move through `edit`, returns [`None`] if the edit touches the moved range
+fn rebase_move(mut data: MoveData, edit: &Edit) -> Option<MoveData> {
+    let (Some(&first), Some(&last)) = (data.indecies.first(), data.indecies.last()) else {
+        return Some(data);
+    };
+
+    // elements out of `lo..hi` keep their positions through the move
+    let amount = data.indecies.len();
+    let lo = data.dest_index.min(first.min(last));
+    let hi = (data.dest_index + amount).max(first.max(last) + 1);
+
+    let shift = match edit {
+        Edit::Insert { index, amount } => {
+            if *index <= lo {
+                *amount as isize
+            } else if *index >= hi {
+                0
+            } else {
+                return None;
+            }
+        }
+        Edit::Remove(removed) => {
+            let below = removed.partition_point(|&r| r < lo);
+
+            if removed.get(below).is_some_and(|&r| r < hi) {
+                return None;
+            }
+
+            -(below as isize)
+        }
+    };
+
+    data.dest_index = data.dest_index.saturating_add_signed(shift);
+
+    for index in data.indecies.iter_mut() {
+        *index = index.saturating_add_signed(shift);
+    }
+
+    return Some(data);
+}
+
+/// Shifts a reordered block through `edit`, returns [`None`] if the edit touches the block
+fn rebase_permute(mut data: PermuteData, edit: &Edit) -> Option<PermuteData> {
+    let end = data.index + data.order.len();
+
+    match edit {
+        Edit::Insert { index, amount } => {
+            if *index <= data.index {
+                data.index += amount;
+            } else if *index < end {
+                return None;
+            }
+        }
+        Edit::Remove(removed) => {
+            let below = removed.partition_point(|&r| r < data.index);
+
+            if removed.get(below).is_some_and(|&r| r < end) {
+                return None;
+            }
+
+            data.index -= below;
+        }
+    }
+
+    return Some(data);
+}
+
+/// Returns the rebased states whose content was changed by their edit
+fn changed_states(states: &[(NodeId, Edit, usize)]) -> Vec<NodeId> {
+    return states.iter().filter(|(_, edit, _)| !edit.is_empty()).map(|&(id, _, _)| id).collect();
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Enables or disables rebasing of history through untracked mutations.
+    /// With rebasing enabled `insert`, `remove`, `push_front`, `pop_back`, `drain`, `extend` and others
+    /// transform indecies of the stored actions instead of wiping history,
+    /// only the actions depending on the touched elements are dropped.
+    /// States changed by the mutation get new ids, checkpoints and the save point of them become invalid
+    #[inline(always)]
+    pub fn set_rebase_history(&mut self, rebase: bool) {
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        self.rebase = rebase;
+    }
+
+    /// Returns true if history is rebased through untracked mutations instead of wiping
+    #[inline(always)]
+    pub fn is_rebase_history(&self) -> bool {
+        self.rebase
+    }
+
+    /// Handles an untracked mutation of the collection of length `len`, the mutation is described by `edit`.
+    /// History is rebased through it in rebase mode or wiped otherwise, selects are shifted and observers are notified
+    pub(super) fn untracked_edit(&mut self, edit: Edit, len: usize) {
+        #[cfg(feature = "journal")]
+        self.journal_edit(&edit);
+
+        self.shift_selects(&edit);
+        self.notify_edit(&edit);
+        self.rebase_or_wipe(edit, len);
+    }
+
+    /// Rebases history and redo sequence through `edit` made to the collection of length `len`,
+    /// history is wiped if rebasing is disabled or a group is open
+    pub(super) fn rebase_or_wipe(&mut self, edit: Edit, len: usize) {
+        // the last action can't absorb the next one over the edit
+        self.break_merge();
+
+        if !self.rebase || self.groups.iter().any(|group| !group.is_empty()) {
+            self.wipe_history();
+            return;
+        }
+
+        if edit.is_empty() {
+            return;
+        }
+
+        // evicting conflicting actions shouldn't touch counters of the rebased ones
+        let budget = self.budget.take();
+
+        let (changed, dropped) = match self.tree.is_some() {
+            true => self.rebase_tree(edit, len),
+            false => self.rebase_line(edit, len),
+        };
+
+        // the content of the rebased states differs from the saved one and checkpoints now
+        self.renew_states(&changed);
+
+        self.budget = budget;
+        self.recount_budget();
+
+        if let Some(budget) = self.budget.as_mut()
+            && budget.limits.keep_evicted
+        {
+            budget.evicted.extend(dropped);
+        }
+    }
+
+    /// Rebases the redo sequence and history in linear mode.
+    /// Returns the states changed by the edit and elements of the dropped actions
+    fn rebase_line(&mut self, edit: Edit, len: usize) -> (Vec<NodeId>, Vec<T>) {
+        let mut changed: Vec<NodeId> = vec![];
+        let mut dropped: Vec<T> = vec![];
+
+        // the next action to redo is the last one
+        let mut redo_edit = edit.clone();
+        let mut post_len = len;
+        let mut pos = self.redo_history.len();
+
+        while pos > 0 {
+            let action = mem::replace(&mut self.redo_history[pos - 1], Action::Group(vec![]));
+            let delta = action_len_delta(&action);
+
+            let (action, pre_edit) = match rebase_action(action, &redo_edit, post_len) {
+                Ok(rebased) => rebased,
+                Err(values) => {
+                    dropped.extend(values);
+                    break;
+                }
+            };
+
+            if !pre_edit.is_empty() {
+                changed.push(self.ids.redo[pos - 1]);
+            }
+
+            self.redo_history[pos - 1] = action;
+            redo_edit = pre_edit;
+            post_len = (post_len as isize - delta) as usize;
+            pos -= 1;
+        }
+
+        // the conflicting action and everything redone after it are unreachable
+        for action in self.redo_history.drain(..pos) {
+            dropped.extend(take_values_from_action(action));
+        }
+        self.ids.redo.drain(..pos);
+
+        let (states, history_dropped) = self.rebase_history(edit, len);
+        dropped.extend(history_dropped);
+
+        changed.extend(changed_states(&states));
+
+        return (changed, dropped);
+    }
+
+    /// Rebases history from the last action, actions up to the first conflicting one are dropped.
+    /// Returns the surviving states with edits and lengths of them and elements of the dropped actions
+    fn rebase_history(&mut self, edit: Edit, len: usize) -> (Vec<(NodeId, Edit, usize)>, Vec<T>) {
+        let mut states: Vec<(NodeId, Edit, usize)> = vec![];
+        let mut dropped: Vec<T> = vec![];
+
+        let mut edit = edit;
+        let mut post_len = len;
+        let mut pos = self.history.len();
+
+        loop {
+            let state = match pos {
+                0 => self.ids.root,
+                _ => self.ids.history[pos - 1],
+            };
+
+            states.push((state, edit.clone(), post_len));
+
+            if pos == 0 {
+                break;
+            }
+
+            let action = mem::replace(&mut self.history[pos - 1], Action::Group(vec![]));
+            let delta = action_len_delta(&action);
+
+            let (action, pre_edit) = match rebase_action(action, &edit, post_len) {
+                Ok(rebased) => rebased,
+                Err(values) => {
+                    dropped.extend(values);
+                    break;
+                }
+            };
+
+            self.history[pos - 1] = action;
+            edit = pre_edit;
+            post_len = (post_len as isize - delta) as usize;
+            pos -= 1;
+        }
+
+        // the state after the conflicting action becomes the root
+        for _ in 0..pos {
+            dropped.extend(self.drop_oldest_action());
+        }
+
+        return (states, dropped);
+    }
+
+    /// Rebases all branches of the undo tree, branches depending on the touched elements are dropped.
+    /// Returns the states changed by the edit and elements of the dropped actions
+    fn rebase_tree(&mut self, edit: Edit, len: usize) -> (Vec<NodeId>, Vec<T>) {
+        let line = self.ids.redo.clone();
+        self.park_redo_line();
+
+        let (states, mut dropped) = self.rebase_history(edit, len);
+        let tree = self.tree.as_mut().unwrap();
+
+        let mut changed = changed_states(&states);
+        let mut stack = states;
+
+        while let Some((id, edit, post_len)) = stack.pop() {
+            let Some(node) = tree.nodes.get(&id) else {
+                continue;
+            };
+
+            for child in node.children.clone() {
+                // children on the active path have no parked action
+                let Some(action) = tree.nodes.get_mut(&child).unwrap().action.take() else {
+                    continue;
+                };
+
+                let delta = action_len_delta(&action);
+
+                match rebase_action(action, &edit, post_len) {
+                    Ok((action, pre_edit)) => {
+                        if !pre_edit.is_empty() {
+                            changed.push(child);
+                        }
+
+                        tree.nodes.get_mut(&child).unwrap().action = Some(action);
+                        stack.push((child, pre_edit, (post_len as isize - delta) as usize));
+                    }
+                    Err(values) => {
+                        dropped.extend(values);
+
+                        for parked in tree.remove_subtree(child) {
+                            dropped.extend(take_values_from_action(parked));
+                        }
+                    }
+                }
+            }
+        }
+
+        // the next action to redo is the last one
+        let reachable: Vec<NodeId> = line
+            .iter()
+            .rev()
+            .copied()
+            .take_while(|id| tree.nodes.contains_key(id))
+            .collect();
+
+        self.load_redo_line(&reachable);
+
+        return (changed, dropped);
+    }
+}
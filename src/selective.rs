@@ -0,0 +1,408 @@
+use super::defines::{Action, ChangeEvent, Edit, MoveData, NodeId, PermuteData, RemoveData, ReplaceData};
+use super::error::VecHistoricError;
+use super::private::*;
+use super::rebase::rebase_action;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+
+/// Inverse of the reverted entry in the current state and the later entries as if it wasn't made
+type Commuted = (Action<()>, Vec<Action<()>>);
+
+/// Returns positions kept by an action with its elements left out, or [`None`] for a command
+pub(super) fn shape<T>(action: &Action<T>) -> Option<Action<()>> {
+    let shape = match action {
+        Action::Remove(data) => Action::Remove(RemoveData {
+            indecies: data.indecies.clone(),
+            values: vec![(); data.values.len()],
+        }),
+        Action::Move(data) => Action::Move(data.clone()),
+        Action::Insert(data) => Action::Insert(data.clone()),
+        Action::PushBack => Action::PushBack,
+        Action::PopBack(_) => Action::PopBack(()),
+        Action::PushFront => Action::PushFront,
+        Action::PopFront(_) => Action::PopFront(()),
+        Action::Reinsert(indecies) => Action::Reinsert(indecies.clone()),
+        Action::MoveBack(data) => Action::MoveBack(data.clone()),
+        Action::Group(actions) => Action::Group(actions.iter().map(shape).collect::<Option<_>>()?),
+        Action::Replace(data) => Action::Replace(ReplaceData {
+            index: data.index,
+            values: vec![(); data.values.len()],
+        }),
+        Action::Permute(data) => Action::Permute(data.clone()),
+        Action::Custom(_) | Action::CustomBack(_) => return None,
+    };
+
+    return Some(shape);
+}
+
+/// Puts elements in the slots of `shape` in order they are taken by `take_values_from_action`
+fn fill<T>(shape: Action<()>, values: &mut impl Iterator<Item = T>) -> Action<T> {
+    match shape {
+        Action::Remove(data) => {
+            return Action::Remove(RemoveData {
+                values: values.take(data.values.len()).collect(),
+                indecies: data.indecies,
+            });
+        }
+        Action::Move(data) => return Action::Move(data),
+        Action::Insert(data) => return Action::Insert(data),
+        Action::PushBack => return Action::PushBack,
+        Action::PopBack(_) => return Action::PopBack(values.next().unwrap()),
+        Action::PushFront => return Action::PushFront,
+        Action::PopFront(_) => return Action::PopFront(values.next().unwrap()),
+        Action::Reinsert(indecies) => return Action::Reinsert(indecies),
+        Action::MoveBack(data) => return Action::MoveBack(data),
+        Action::Group(actions) => {
+            return Action::Group(actions.into_iter().map(|action| fill(action, values)).collect());
+        }
+        Action::Replace(data) => {
+            return Action::Replace(ReplaceData {
+                index: data.index,
+                values: values.take(data.values.len()).collect(),
+            });
+        }
+        Action::Permute(data) => return Action::Permute(data),
+        Action::Custom(_) | Action::CustomBack(_) => unreachable!(),
+    }
+}
+
+/// Returns the count of positions kept by an action
+fn footprint(action: &Action<()>) -> usize {
+    match action {
+        Action::Remove(data) => return data.indecies.len(),
+        Action::Insert(data) => return data.amount,
+        Action::Reinsert(indecies) => return indecies.len(),
+        Action::Move(data) | Action::MoveBack(data) => return data.indecies.len(),
+        Action::Replace(data) => return data.values.len(),
+        Action::Permute(data) => return data.order.len(),
+        Action::PushBack | Action::PushFront | Action::PopBack(_) | Action::PopFront(_) => return 1,
+        Action::Group(actions) => return actions.iter().map(footprint).sum(),
+        Action::Custom(_) | Action::CustomBack(_) => return 0,
+    }
+}
+
+/// Splits ascending positions of inserted elements into insertions of consecutive ones made in turn
+fn insert_runs(positions: &[usize]) -> Vec<Edit> {
+    return runs(positions.iter().copied())
+        .into_iter()
+        .map(|run| Edit::Insert {
+            index: run.start,
+            amount: run.len(),
+        })
+        .collect();
+}
+
+/// Describes a change made to the collection of length `len` as edits made in turn,
+/// `action` is the inverse of the change and isn't a group.
+/// Returns [`None`] for a command
+fn forward_edits(action: &Action<()>, len: usize) -> Option<Vec<Edit>> {
+    let edits = match action {
+        Action::Insert(data) => vec![Edit::Insert {
+            index: data.index,
+            amount: data.amount,
+        }],
+        Action::PushBack => vec![Edit::Insert { index: len, amount: 1 }],
+        Action::PushFront => vec![Edit::Insert { index: 0, amount: 1 }],
+        Action::Reinsert(indecies) => insert_runs(&indecies.iter().rev().copied().collect::<Vec<_>>()),
+        Action::Remove(data) => vec![Edit::Remove(data.indecies.iter().rev().copied().collect())],
+        Action::PopBack(_) => vec![Edit::Remove(vec![len - 1])],
+        Action::PopFront(_) => vec![Edit::Remove(vec![0])],
+        // a replacement, a reordering or a move takes elements out and puts them in again
+        Action::Replace(data) => vec![
+            Edit::Remove((data.index..data.index + data.values.len()).collect()),
+            Edit::Insert {
+                index: data.index,
+                amount: data.values.len(),
+            },
+        ],
+        Action::Permute(data) => vec![
+            Edit::Remove((data.index..data.index + data.order.len()).collect()),
+            Edit::Insert {
+                index: data.index,
+                amount: data.order.len(),
+            },
+        ],
+        Action::Move(data) => vec![
+            Edit::Remove(data.indecies.clone()),
+            Edit::Insert {
+                index: data.dest_index,
+                amount: data.indecies.len(),
+            },
+        ],
+        Action::MoveBack(data) => {
+            let mut edits = vec![Edit::Remove((data.dest_index..data.dest_index + data.indecies.len()).collect())];
+            edits.extend(insert_runs(&data.indecies));
+            edits
+        }
+        Action::Group(_) | Action::Custom(_) | Action::CustomBack(_) => return None,
+    };
+
+    return Some(edits);
+}
+
+/// Describes a change made to the collection of length `len` as edits made in turn with the length before each of them,
+/// `action` is the inverse of the change. Returns [`None`] if it holds a command
+pub(super) fn change_edits(action: &Action<()>, len: usize) -> Option<Vec<(Edit, usize)>> {
+    let mut edits: Vec<(Edit, usize)> = vec![];
+    let mut len = len;
+
+    let Action::Group(actions) = action else {
+        for edit in forward_edits(action, len)? {
+            let delta = edit.len_delta();
+
+            edits.push((edit, len));
+            len = (len as isize + delta) as usize;
+        }
+
+        return Some(edits);
+    };
+
+    // inverses of a group are kept in order the changes were made
+    for action in actions {
+        edits.extend(change_edits(action, len)?);
+        len = (len as isize + action_len_delta(action)) as usize;
+    }
+
+    return Some(edits);
+}
+
+/// Makes the inverse of a change out of `edits` returned by `forward_edits` for its old inverse `action`,
+/// `len` is the length before the edits. Returns [`None`] if the edits don't make such a change anymore
+fn rebuild(action: Action<()>, edits: Vec<Edit>, len: usize) -> Option<Action<()>> {
+    let mut removed: Vec<usize> = vec![];
+    let mut inserted: Vec<usize> = vec![];
+
+    for edit in edits {
+        match edit {
+            Edit::Remove(indecies) => removed = indecies,
+            Edit::Insert { index, amount } => inserted.extend(index..index + amount),
+        }
+    }
+
+    let contiguous = |indecies: &[usize]| indecies.windows(2).all(|pair| pair[1] == pair[0] + 1);
+
+    let rebuilt = match action {
+        Action::PushBack if inserted == [len] => Action::PushBack,
+        Action::PushFront if inserted == [0] => Action::PushFront,
+        Action::PushBack | Action::PushFront | Action::Insert(_) | Action::Reinsert(_) => inserted_action(inserted),
+        Action::PopBack(_) if removed.len() == 1 && removed[0] + 1 == len => Action::PopBack(()),
+        Action::PopFront(_) if removed == [0] => Action::PopFront(()),
+        Action::PopBack(_) | Action::PopFront(_) | Action::Remove(_) => Action::Remove(RemoveData {
+            values: vec![(); removed.len()],
+            indecies: removed.into_iter().rev().collect(),
+        }),
+        Action::Replace(data) => {
+            // replaced elements have to stay consecutive
+            if removed != inserted {
+                return None;
+            }
+
+            Action::Replace(ReplaceData {
+                index: inserted.first().copied().unwrap_or(0),
+                values: data.values,
+            })
+        }
+        Action::Permute(data) => {
+            // the reordered block has to stay in one place
+            if removed != inserted {
+                return None;
+            }
+
+            Action::Permute(PermuteData {
+                index: inserted.first().copied().unwrap_or(0),
+                order: data.order,
+            })
+        }
+        Action::Move(_) => Action::Move(MoveData {
+            dest_index: inserted.first().copied().unwrap_or(0),
+            indecies: removed,
+        }),
+        Action::MoveBack(_) => {
+            if !contiguous(&removed) {
+                return None;
+            }
+
+            Action::MoveBack(MoveData {
+                dest_index: removed.first().copied().unwrap_or(0),
+                indecies: inserted,
+            })
+        }
+        Action::Group(_) | Action::Custom(_) | Action::CustomBack(_) => return None,
+    };
+
+    return Some(rebuilt);
+}
+
+/// Swaps a change with the change made right after it.
+/// `reverted` and `later` are their inverses, `len` is the length between them.
+/// Returns the inverses as if `later` was made first, or [`None`] if `later` depends on `reverted`
+fn commute(reverted: Action<()>, later: Action<()>, len: usize) -> Option<(Action<()>, Action<()>)> {
+    if let Action::Group(actions) = later {
+        let mut reverted = reverted;
+        let mut len = len;
+        let mut commuted: Vec<Action<()>> = Vec::with_capacity(actions.len());
+
+        for action in actions {
+            let delta = action_len_delta(&action);
+            let (rebased, action) = commute(reverted, action, len)?;
+
+            reverted = rebased;
+            commuted.push(action);
+            len = (len as isize + delta) as usize;
+        }
+
+        return Some((reverted, Action::Group(commuted)));
+    }
+
+    let base_len = (len as isize - action_len_delta(&reverted)) as usize;
+    let kept = footprint(&reverted);
+
+    let mut reverted = reverted;
+    let mut post_len = len;
+    let mut pre_edits: Vec<Edit> = vec![];
+
+    for edit in forward_edits(&later, len)? {
+        let (rebased, pre_edit) = rebase_action(reverted, &edit, post_len).ok()?;
+
+        // the later change took out an element kept by the reverted one
+        if footprint(&rebased) != kept {
+            return None;
+        }
+
+        post_len = (post_len as isize + edit.len_delta()) as usize;
+        reverted = rebased;
+        pre_edits.push(pre_edit);
+    }
+
+    return Some((reverted, rebuild(later, pre_edits, base_len)?));
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Moves the entry at `index` of history sequence after the later entries.
+    /// Returns its inverse in the current state with the later entries as if it wasn't made,
+    /// or [`None`] if it's the last entry
+    fn commute_to_current(&self, index: usize) -> Result<Option<Commuted>, VecHistoricError> {
+        if index + 1 == self.history.len() {
+            return Ok(None);
+        }
+
+        let mut len = self.data.len();
+
+        for action in self.history.range(index + 1..) {
+            len = (len as isize - action_len_delta(action)) as usize;
+        }
+
+        let conflict = |index: usize| VecHistoricError::Conflict { index };
+
+        // positions kept by a command are unknown
+        let mut reverted = shape(&self.history[index]).ok_or(conflict(index + 1))?;
+        let mut commuted: Vec<Action<()>> = Vec::with_capacity(self.history.len() - index - 1);
+
+        for (later_index, action) in self.history.iter().enumerate().skip(index + 1) {
+            let delta = action_len_delta(action);
+            let later = shape(action).ok_or(conflict(later_index))?;
+            let (rebased, later) = commute(reverted, later, len).ok_or(conflict(later_index))?;
+
+            reverted = rebased;
+            commuted.push(later);
+            len = (len as isize + delta) as usize;
+        }
+
+        return Ok(Some((reverted, commuted)));
+    }
+
+    /// Reverts only the entry at `index` of history sequence (0 is the oldest), the later entries stay
+    /// and their positions are transformed as if the reverted entry wasn't made.
+    /// The reverted entry is dropped, the redo sequence is wiped, in undo tree mode branches
+    /// out of the states after the reverted entry are dropped with it.
+    /// Observers get the changes made by the revert followed by `Undone`
+    /// Returns an error if a later entry depends on the reverted one, e.g. it removes, replaces or moves elements
+    /// put by it, or if a group is open. The collection is left unchanged then
+    pub fn undo_at(&mut self, index: usize) -> Result<(), VecHistoricError> {
+        if !self.groups.is_empty() {
+            return Err(VecHistoricError::GroupOpen);
+        }
+
+        if index >= self.history.len() {
+            return Err(VecHistoricError::IndexOutOfBounds {
+                index,
+                len: self.history.len(),
+            });
+        }
+
+        let commuted = self.commute_to_current(index)?;
+
+        let mut taken: Vec<Action<T>> = Vec::with_capacity(self.history.len() - index);
+
+        while self.history.len() > index {
+            taken.push(self.history_pop().unwrap());
+        }
+
+        let old_ids: Vec<NodeId> = self.ids.history.drain(index..).collect();
+        let reverted = taken.pop().unwrap();
+
+        // elements are put back in the reshaped actions in order they were taken
+        let (reverted, later) = match commuted {
+            None => (reverted, vec![]),
+            Some((shape, shapes)) => {
+                let reverted = fill(shape, &mut take_values_from_action(reverted).into_iter());
+
+                let later: Vec<Action<T>> = shapes
+                    .into_iter()
+                    .zip(taken.into_iter().rev())
+                    .map(|(shape, action)| fill(shape, &mut take_values_from_action(action).into_iter()))
+                    .collect();
+
+                (reverted, later)
+            }
+        };
+
+        let inverse = self.handle_action(reverted);
+        self.notify_done(&inverse);
+        self.notify(ChangeEvent::Undone);
+
+        // the states after the reverted entry don't exist anymore
+        let mut parent = self.ids.current();
+
+        match self.tree.as_mut() {
+            Some(_) => {
+                self.park_redo_line();
+                self.tree.as_mut().unwrap().remove_subtree(old_ids[0]);
+            }
+            None => {
+                self.redo_history.clear();
+                self.ids.redo.clear();
+            }
+        }
+
+        self.meta.entries.remove(&old_ids[0]);
+
+        for (action, &old) in later.into_iter().zip(old_ids[1..].iter()) {
+            let id = self.ids.make_id();
+
+            if let Some(tree) = self.tree.as_mut() {
+                tree.add_node(id, parent);
+            }
+
+            self.ids.history.push_back(id);
+            self.history_push(action);
+
+            if let Some(meta) = self.meta.entries.remove(&old) {
+                self.meta.entries.insert(id, meta);
+            }
+
+            parent = id;
+        }
+
+        if self.saved.is_some_and(|saved| old_ids.contains(&saved)) {
+            self.saved = None;
+        }
+
+        self.break_merge();
+
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,73 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::defines::Action;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+use super::view::has_command;
+
+/// Serialized form of VecHistoric: content, selects in their order, history and redo sequences
+#[derive(Serialize)]
+struct VecHistoricRef<'a, T> {
+    data: Vec<&'a T>,
+    selects: Vec<usize>,
+    history: Vec<&'a Action<T>>,
+    redo: Vec<&'a Action<T>>,
+}
+
+#[derive(Deserialize)]
+struct VecHistoricRepr<T> {
+    data: Vec<T>,
+    selects: Vec<usize>,
+    history: Vec<Action<T>>,
+    #[serde(default = "Vec::new")]
+    redo: Vec<Action<T>>,
+}
+
+/// Undo tree branches, open groups, history limits, the merge policy, checkpoints and metadata of entries aren't serialized.
+/// Fails if history or redo sequence holds a custom command, commands can't be serialized
+impl<T: Serialize, S: Storage<T>> Serialize for VecHistoric<T, S> {
+    fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+        if self.history.iter().chain(&self.redo_history).any(has_command) {
+            return Err(serde::ser::Error::custom("history holds a custom command, commands can't be serialized"));
+        }
+
+        let repr = VecHistoricRef {
+            data: self.data.iter().collect(),
+            selects: self.selects.iter().copied().collect(),
+            history: self.history.iter().collect(),
+            redo: self.redo_history.iter().collect(),
+        };
+
+        return repr.serialize(serializer);
+    }
+}
+
+/// Fails if history, redo sequence or selects don't fit the length of the content.
+/// The restored state is marked as saved
+impl<'de, T: Deserialize<'de>, S: Storage<T>> Deserialize<'de> for VecHistoric<T, S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = VecHistoricRepr::<T>::deserialize(deserializer)?;
+
+        let mut historic: VecHistoric<T, S> = repr.data.into_iter().collect();
+
+        historic.selects = repr.selects.into_iter().collect();
+
+        for action in repr.history {
+            let id = historic.ids.make_id();
+            historic.ids.history.push_back(id);
+            historic.history.push_back(action);
+        }
+
+        for action in repr.redo {
+            let id = historic.ids.make_id();
+            historic.ids.redo.push(id);
+            historic.redo_history.push(action);
+        }
+
+        historic.validate_history().map_err(D::Error::custom)?;
+        historic.mark_saved();
+
+        return Ok(historic);
+    }
+}
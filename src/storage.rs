@@ -0,0 +1,341 @@
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut, Range};
+
+use gapbuf::{GapBuffer, Slice};
+
+/// Sequence which keeps elements of `VecHistoric`.
+/// Implemented for `GapBuffer`, `Vec`, `VecDeque` and `ChunkedVec`
+pub trait Storage<T>:
+    Index<usize, Output = T>
+    + IndexMut<usize>
+    + Default
+    + Extend<T>
+    + FromIterator<T>
+    + IntoIterator<Item = T, IntoIter: DoubleEndedIterator>
+{
+    type Iter<'a>: DoubleEndedIterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    type IterMut<'a>: DoubleEndedIterator<Item = &'a mut T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    type Drain<'a>: Iterator<Item = T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Creates an empty sequence with the specified capacity (if supported)
+    fn with_capacity(cap: usize) -> Self;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    fn push_back(&mut self, value: T);
+
+    fn push_front(&mut self, value: T);
+
+    fn pop_back(&mut self) -> Option<T>;
+
+    fn pop_front(&mut self) -> Option<T>;
+
+    fn insert(&mut self, index: usize, value: T);
+
+    /// Inserts elements of `iter` starting from `index`
+    fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>);
+
+    fn remove(&mut self, index: usize) -> T;
+
+    /// Removes the `range`, elements are removed even if the iterator isn't consumed
+    fn drain(&mut self, range: Range<usize>) -> Self::Drain<'_>;
+
+    fn clear(&mut self);
+
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_>;
+}
+
+/// Elements of the collection given to `HistoricCommand`, the count of elements can't be changed through it
+pub trait Elements<T> {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    fn get(&self, index: usize) -> Option<&T>;
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+
+    fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = &T> + '_>;
+
+    fn iter_mut(&mut self) -> Box<dyn DoubleEndedIterator<Item = &mut T> + '_>;
+}
+
+/// Storage seen as `Elements`, a wrapper keeps methods of both traits from clashing on storage types
+pub(crate) struct StorageElements<'a, S>(pub(crate) &'a mut S);
+
+impl<T, S: Storage<T>> Elements<T> for StorageElements<'_, S> {
+    fn len(&self) -> usize {
+        return self.0.len();
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.0.len() {
+            return None;
+        }
+
+        return Some(&self.0[index]);
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.0.len() {
+            return None;
+        }
+
+        return Some(&mut self.0[index]);
+    }
+
+    fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = &T> + '_> {
+        return Box::new(self.0.iter());
+    }
+
+    fn iter_mut(&mut self) -> Box<dyn DoubleEndedIterator<Item = &mut T> + '_> {
+        return Box::new(self.0.iter_mut());
+    }
+}
+
+impl<T> Index<usize> for dyn Elements<T> + '_ {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        return self.get(index).expect("index out of bounds");
+    }
+}
+
+impl<T> IndexMut<usize> for dyn Elements<T> + '_ {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        return self.get_mut(index).expect("index out of bounds");
+    }
+}
+
+impl<T> Storage<T> for GapBuffer<T> {
+    type Iter<'a>
+        = gapbuf::Iter<'a, T>
+    where
+        T: 'a;
+
+    type IterMut<'a>
+        = gapbuf::IterMut<'a, T>
+    where
+        T: 'a;
+
+    type Drain<'a>
+        = gapbuf::Drain<'a, T>
+    where
+        T: 'a;
+
+    fn with_capacity(cap: usize) -> Self {
+        return GapBuffer::with_capacity(cap);
+    }
+
+    fn len(&self) -> usize {
+        return Slice::len(self);
+    }
+
+    fn push_back(&mut self, value: T) {
+        GapBuffer::push_back(self, value);
+    }
+
+    fn push_front(&mut self, value: T) {
+        GapBuffer::push_front(self, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        return GapBuffer::pop_back(self);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        return GapBuffer::pop_front(self);
+    }
+
+    fn insert(&mut self, index: usize, value: T) {
+        GapBuffer::insert(self, index, value);
+    }
+
+    fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
+        GapBuffer::insert_many(self, index, iter);
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        return GapBuffer::remove(self, index);
+    }
+
+    fn drain(&mut self, range: Range<usize>) -> Self::Drain<'_> {
+        return GapBuffer::drain(self, range);
+    }
+
+    fn clear(&mut self) {
+        GapBuffer::clear(self);
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        return Slice::iter(self);
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        return Slice::iter_mut(self);
+    }
+}
+
+/// Front operations are linear in the length
+impl<T> Storage<T> for Vec<T> {
+    type Iter<'a>
+        = std::slice::Iter<'a, T>
+    where
+        T: 'a;
+
+    type IterMut<'a>
+        = std::slice::IterMut<'a, T>
+    where
+        T: 'a;
+
+    type Drain<'a>
+        = std::vec::Drain<'a, T>
+    where
+        T: 'a;
+
+    fn with_capacity(cap: usize) -> Self {
+        return Vec::with_capacity(cap);
+    }
+
+    fn len(&self) -> usize {
+        return Vec::len(self);
+    }
+
+    fn push_back(&mut self, value: T) {
+        self.push(value);
+    }
+
+    fn push_front(&mut self, value: T) {
+        Vec::insert(self, 0, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        return self.pop();
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if Vec::is_empty(self) {
+            return None;
+        }
+
+        return Some(Vec::remove(self, 0));
+    }
+
+    fn insert(&mut self, index: usize, value: T) {
+        Vec::insert(self, index, value);
+    }
+
+    fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
+        self.splice(index..index, iter);
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        return Vec::remove(self, index);
+    }
+
+    fn drain(&mut self, range: Range<usize>) -> Self::Drain<'_> {
+        return Vec::drain(self, range);
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        return self.as_slice().iter();
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        return self.as_mut_slice().iter_mut();
+    }
+}
+
+impl<T> Storage<T> for VecDeque<T> {
+    type Iter<'a>
+        = std::collections::vec_deque::Iter<'a, T>
+    where
+        T: 'a;
+
+    type IterMut<'a>
+        = std::collections::vec_deque::IterMut<'a, T>
+    where
+        T: 'a;
+
+    type Drain<'a>
+        = std::collections::vec_deque::Drain<'a, T>
+    where
+        T: 'a;
+
+    fn with_capacity(cap: usize) -> Self {
+        return VecDeque::with_capacity(cap);
+    }
+
+    fn len(&self) -> usize {
+        return VecDeque::len(self);
+    }
+
+    fn push_back(&mut self, value: T) {
+        VecDeque::push_back(self, value);
+    }
+
+    fn push_front(&mut self, value: T) {
+        VecDeque::push_front(self, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        return VecDeque::pop_back(self);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        return VecDeque::pop_front(self);
+    }
+
+    fn insert(&mut self, index: usize, value: T) {
+        VecDeque::insert(self, index, value);
+    }
+
+    fn insert_many(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
+        let mut tail = self.split_off(index);
+        self.extend(iter);
+        self.append(&mut tail);
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        return VecDeque::remove(self, index).expect("index out of bounds");
+    }
+
+    fn drain(&mut self, range: Range<usize>) -> Self::Drain<'_> {
+        return VecDeque::drain(self, range);
+    }
+
+    fn clear(&mut self) {
+        VecDeque::clear(self);
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        return VecDeque::iter(self);
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        return VecDeque::iter_mut(self);
+    }
+}
@@ -1,69 +1,64 @@
-use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
 use std::ops::{Index, IndexMut};
 
 use gapbuf::GapBuffer;
-use indexmap::IndexSet;
 
+use super::defines::Edit;
 use super::vec_historic::VecHistoric;
-use super::defines::{Iter, IterMut, IntoIter};
+use super::defines::IntoIter;
+use super::storage::Storage;
 
-impl<T: PartialEq> PartialEq for VecHistoric<T> {
+impl<T: PartialEq, S: Storage<T>> PartialEq for VecHistoric<T, S> {
     fn eq(&self, other: &Self) -> bool {
-        self.data == other.data
+        self.data.len() == other.data.len() && self.data.iter().eq(other.data.iter())
     }
 }
 
-impl<T: Eq> Eq for VecHistoric<T> {}
+impl<T: Eq, S: Storage<T>> Eq for VecHistoric<T, S> {}
 
-impl<T: PartialOrd> PartialOrd for VecHistoric<T> {
+impl<T: PartialOrd, S: Storage<T>> PartialOrd for VecHistoric<T, S> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.data.partial_cmp(&other.data)
+        self.data.iter().partial_cmp(other.data.iter())
     }
 }
 
-impl<T: Ord> Ord for VecHistoric<T> {
+impl<T: Ord, S: Storage<T>> Ord for VecHistoric<T, S> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.data.cmp(&other.data)
+        self.data.iter().cmp(other.data.iter())
     }
 }
 
-impl<T: Hash> Hash for VecHistoric<T> {
+impl<T: Hash, S: Storage<T>> Hash for VecHistoric<T, S> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.data.hash(state);
+        self.data.iter().for_each(|value| value.hash(state));
     }
 }
 
 impl<T> From<GapBuffer<T>> for VecHistoric<T> {
     fn from(other: GapBuffer<T>) -> Self {
-        Self { 
-            data: GapBuffer::from(other),
-            selects: IndexSet::new(),
-            history: VecDeque::new()
-        }
+        Self::from_data(other)
     }
 }
 
-impl<T> FromIterator<T> for VecHistoric<T> {
+impl<T, S: Storage<T>> FromIterator<T> for VecHistoric<T, S> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        Self {
-            data: GapBuffer::from_iter(iter),
-            selects: IndexSet::new(),
-            history: VecDeque::new()
-        }
+        Self::from_data(S::from_iter(iter))
     }
 }
 
-impl<T> Extend<T> for VecHistoric<T> {
+impl<T, S: Storage<T>> Extend<T> for VecHistoric<T, S> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.clear_history();
+        let len = self.data.len();
         self.data.extend(iter);
+
+        let amount = self.data.len() - len;
+        self.untracked_edit(Edit::Insert { index: len, amount }, len);
     }
 }
 
-impl<T> IntoIterator for VecHistoric<T> {
+impl<T, S: Storage<T>> IntoIterator for VecHistoric<T, S> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, S>;
 
     fn into_iter(self) -> Self::IntoIter {
         // self.data.into_iter()
@@ -71,25 +66,25 @@ impl<T> IntoIterator for VecHistoric<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a VecHistoric<T> {
+impl<'a, T, S: Storage<T>> IntoIterator for &'a VecHistoric<T, S> {
     type Item = &'a T;
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = S::Iter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.data.iter()
     }
 }
 
-impl<'a, T> IntoIterator for &'a mut VecHistoric<T> {
+impl<'a, T, S: Storage<T>> IntoIterator for &'a mut VecHistoric<T, S> {
     type Item = &'a mut T;
-    type IntoIter = IterMut<'a, T>;
+    type IntoIter = S::IterMut<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.data.iter_mut()
     }
 }
 
-impl<T> Index<usize> for VecHistoric<T> {
+impl<T, S: Storage<T>> Index<usize> for VecHistoric<T, S> {
     type Output = T;
 
     fn index(&self, idx: usize) -> &Self::Output {
@@ -97,8 +92,11 @@ impl<T> Index<usize> for VecHistoric<T> {
     }
 }
 
-impl<T> IndexMut<usize> for VecHistoric<T> {
+impl<T, S: Storage<T>> IndexMut<usize> for VecHistoric<T, S> {
     fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
         &mut self.data[idx]
     }
 }
\ No newline at end of file
@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+
+use super::defines::{Action, ActionMeta, HistoryIds, NodeId, UndoNode, UndoTree};
+use super::private::*;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+
+impl<T> UndoTree<T> {
+    /// Creates a tree of a single branch:
+    /// the root followed by nodes of history sequence and nodes of redo sequence
+    pub(super) fn new(ids: &HistoryIds) -> Self {
+        let mut tree = UndoTree {
+            nodes: HashMap::new(),
+        };
+
+        tree.nodes.insert(
+            ids.root,
+            UndoNode {
+                parent: None,
+                children: vec![],
+                action: None,
+            },
+        );
+
+        let mut parent = ids.root;
+
+        // the next node to redo is the last one
+        for &id in ids.history.iter().chain(ids.redo.iter().rev()) {
+            tree.add_node(id, parent);
+            parent = id;
+        }
+
+        return tree;
+    }
+
+    /// Adds a node `id` as a child of `parent`
+    pub(super) fn add_node(&mut self, id: NodeId, parent: NodeId) {
+        self.nodes.insert(
+            id,
+            UndoNode {
+                parent: Some(parent),
+                children: vec![],
+                action: None,
+            },
+        );
+        self.nodes.get_mut(&parent).unwrap().children.push(id);
+    }
+
+    /// Returns the path from the root (excluded) to the node `id` (included)
+    pub(super) fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path: Vec<NodeId> = vec![];
+        let mut node = id;
+
+        while let Some(parent) = self.nodes[&node].parent {
+            path.push(node);
+            node = parent;
+        }
+
+        path.reverse();
+
+        return path;
+    }
+
+    /// Makes `new_root` the root, drops the old root with all branches out of the new root.
+    /// Returns parked actions of the dropped nodes
+    pub(super) fn evict_root(&mut self, old_root: NodeId, new_root: NodeId) -> Vec<Action<T>> {
+        let mut actions: Vec<Action<T>> = vec![];
+        let mut stack: Vec<NodeId> = vec![old_root];
+
+        while let Some(id) = stack.pop() {
+            let node = self.nodes.remove(&id).unwrap();
+
+            if let Some(action) = node.action {
+                actions.push(action);
+            }
+
+            stack.extend(node.children.into_iter().filter(|&child| child != new_root));
+        }
+
+        self.nodes.get_mut(&new_root).unwrap().parent = None;
+
+        return actions;
+    }
+
+    /// Changes id of a node keeping its links
+    pub(super) fn rename(&mut self, old: NodeId, new: NodeId) {
+        let node = self.nodes.remove(&old).unwrap();
+
+        if let Some(parent) = node.parent {
+            let children = &mut self.nodes.get_mut(&parent).unwrap().children;
+            let pos = children.iter().position(|&child| child == old).unwrap();
+            children[pos] = new;
+        }
+
+        for child in node.children.iter() {
+            self.nodes.get_mut(child).unwrap().parent = Some(new);
+        }
+
+        self.nodes.insert(new, node);
+    }
+
+    /// Returns the oldest parked branch, its first node has a parked action and its parent has none
+    pub(super) fn oldest_branch(&self) -> Option<NodeId> {
+        let branch = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.action.is_some())
+            .filter(|(_, node)| node.parent.is_some_and(|parent| self.nodes[&parent].action.is_none()))
+            .map(|(&id, _)| id)
+            .min();
+
+        return branch;
+    }
+
+    /// Drops the node `id` with all nodes below it.
+    /// Returns parked actions of the dropped nodes
+    pub(super) fn remove_subtree(&mut self, id: NodeId) -> Vec<Action<T>> {
+        if let Some(parent) = self.nodes[&id].parent {
+            self.nodes.get_mut(&parent).unwrap().children.retain(|&child| child != id);
+        }
+
+        let mut actions: Vec<Action<T>> = vec![];
+        let mut stack: Vec<NodeId> = vec![id];
+
+        while let Some(id) = stack.pop() {
+            let node = self.nodes.remove(&id).unwrap();
+
+            if let Some(action) = node.action {
+                actions.push(action);
+            }
+
+            stack.extend(node.children);
+        }
+
+        return actions;
+    }
+
+    /// Drops every node except `current`, which becomes the root and keeps its id.
+    /// Returns parked actions of the dropped nodes
+    pub(super) fn compact(&mut self, current: NodeId) -> Vec<Action<T>> {
+        let mut actions: Vec<Action<T>> = vec![];
+
+        let dropped: Vec<NodeId> = self.nodes.keys().copied().filter(|&id| id != current).collect();
+
+        for id in dropped {
+            if let Some(action) = self.nodes.remove(&id).unwrap().action {
+                actions.push(action);
+            }
+        }
+
+        let root = self.nodes.get_mut(&current).unwrap();
+        root.parent = None;
+        root.children.clear();
+
+        return actions;
+    }
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Switches history to undo tree mode.
+    /// New actions don't wipe the redo sequence anymore, it's kept as a branch of the tree
+    pub fn enable_undo_tree(&mut self) {
+        if self.tree.is_none() {
+            self.tree = Some(UndoTree::new(&self.ids));
+        }
+    }
+
+    /// Switches history back to linear mode and returns all elements of the branches out of the active one
+    pub fn disable_undo_tree(&mut self) -> Vec<T> {
+        let Some(tree) = self.tree.take() else {
+            return vec![];
+        };
+
+        let mut values: Vec<T> = vec![];
+
+        for (_, node) in tree.nodes {
+            if let Some(action) = node.action {
+                values.extend(take_values_from_action(action));
+            }
+        }
+
+        return values;
+    }
+
+    /// Returns true if history is in undo tree mode
+    #[inline(always)]
+    pub fn is_undo_tree(&self) -> bool {
+        self.tree.is_some()
+    }
+
+    /// Returns the node of the current state
+    #[inline(always)]
+    pub fn current_node(&self) -> NodeId {
+        self.ids.current()
+    }
+
+    /// Returns the node of the state before the first action of history
+    #[inline(always)]
+    pub fn root_node(&self) -> NodeId {
+        self.ids.root
+    }
+
+    /// Returns the parent of a node or [`None`] if the node is the root or doesn't exist
+    /// Always returns [`None`] in linear mode
+    pub fn node_parent(&self, id: NodeId) -> Option<NodeId> {
+        self.tree.as_ref()?.nodes.get(&id)?.parent
+    }
+
+    /// Returns children of a node in creation order
+    /// Always returns an empty slice in linear mode
+    pub fn node_children(&self, id: NodeId) -> &[NodeId] {
+        let Some(node) = self.tree.as_ref().and_then(|tree| tree.nodes.get(&id)) else {
+            return &[];
+        };
+
+        return &node.children;
+    }
+
+    /// Returns metadata of the entry which reaches a node, the root has none
+    pub fn node_meta(&self, id: NodeId) -> Option<&ActionMeta> {
+        if id == self.ids.root || !self.is_state_reachable(id) {
+            return None;
+        }
+
+        return self.meta.entries.get(&id);
+    }
+
+    /// Returns the last nodes of all branches in creation order
+    /// Always returns an empty vec in linear mode
+    pub fn branches(&self) -> Vec<NodeId> {
+        let Some(tree) = self.tree.as_ref() else {
+            return vec![];
+        };
+
+        let mut leaves: Vec<NodeId> = tree
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.children.is_empty())
+            .map(|(&id, _)| id)
+            .collect();
+
+        leaves.sort();
+
+        return leaves;
+    }
+
+    /// Switches to the next sibling of the current node.
+    /// Returns the new current node or [`None`] if there is no next sibling
+    pub fn next_branch(&mut self) -> Option<NodeId> {
+        let sibling = self.sibling_of_current(1)?;
+        self.jump_to_node(sibling);
+        return Some(sibling);
+    }
+
+    /// Switches to the previous sibling of the current node.
+    /// Returns the new current node or [`None`] if there is no previous sibling
+    pub fn prev_branch(&mut self) -> Option<NodeId> {
+        let sibling = self.sibling_of_current(-1)?;
+        self.jump_to_node(sibling);
+        return Some(sibling);
+    }
+
+    /// Brings the collection to the state of the node `id` undoing and redoing actions on the way.
+    /// The redo sequence follows the latest branch below the node afterwards.
+    /// Returns false if the node doesn't exist, history is in linear mode or a group is open
+    pub fn jump_to_node(&mut self, id: NodeId) -> bool {
+        let Some(tree) = self.tree.as_ref() else {
+            return false;
+        };
+
+        if !tree.nodes.contains_key(&id) || !self.groups.is_empty() {
+            return false;
+        }
+
+        let target_path = tree.path_to(id);
+        let on_path: HashSet<NodeId> = target_path.iter().copied().collect();
+
+        // going up to the common ancestor
+        loop {
+            let current = self.ids.current();
+
+            if current == self.ids.root || on_path.contains(&current) {
+                break;
+            }
+
+            self.undo_step();
+        }
+
+        let tree = self.tree.as_ref().unwrap();
+        let current = self.ids.current();
+
+        let start = target_path
+            .iter()
+            .position(|&node| node == current)
+            .map_or(0, |pos| pos + 1);
+
+        let mut line: Vec<NodeId> = target_path[start..].to_vec();
+
+        let mut last = id;
+        while let Some(&child) = tree.nodes[&last].children.last() {
+            line.push(child);
+            last = child;
+        }
+
+        self.park_redo_line();
+        self.load_redo_line(&line);
+
+        // going down to the target
+        for _ in start..target_path.len() {
+            self.redo_step();
+        }
+
+        self.enforce_history_limits();
+
+        #[cfg(feature = "journal")]
+        self.journal_stale();
+
+        return true;
+    }
+
+    fn sibling_of_current(&self, offset: isize) -> Option<NodeId> {
+        if !self.groups.is_empty() {
+            return None;
+        }
+
+        let tree = self.tree.as_ref()?;
+        let current = self.ids.current();
+        let parent = tree.nodes[&current].parent?;
+
+        let siblings = &tree.nodes[&parent].children;
+        let pos = siblings.iter().position(|&node| node == current).unwrap();
+
+        let sibling = pos.checked_add_signed(offset)?;
+
+        return siblings.get(sibling).copied();
+    }
+
+    /// Moves actions of the redo sequence into their nodes of the tree
+    pub(super) fn park_redo_line(&mut self) {
+        let Some(tree) = self.tree.as_mut() else {
+            return;
+        };
+
+        for (id, action) in self.ids.redo.drain(..).zip(self.redo_history.drain(..)) {
+            tree.nodes.get_mut(&id).unwrap().action = Some(action);
+        }
+    }
+
+    /// Fills the redo sequence with parked actions of `line`, the first node is redone first
+    pub(super) fn load_redo_line(&mut self, line: &[NodeId]) {
+        let tree = self.tree.as_mut().unwrap();
+
+        for &id in line.iter().rev() {
+            let action = tree.nodes.get_mut(&id).unwrap().action.take().unwrap();
+
+            self.redo_history.push(action);
+            self.ids.redo.push(id);
+        }
+    }
+}
@@ -1,13 +1,26 @@
 use std::collections::VecDeque;
 
 use gapbuf::GapBuffer;
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 
-use super::defines::Action;
+use super::defines::{Action, HistoryBudget, HistoryIds, HistoryMeta, JournalSlot, MergeState, NodeId, Observers, UndoTree};
 
 #[derive(Debug, Clone)]
-pub struct VecHistoric<T> {
-    pub(super) data: GapBuffer<T>,
+pub struct VecHistoric<T, S = GapBuffer<T>> {
+    pub(super) data: S, // elements of the collection
     pub(super) selects: IndexSet<usize>,
     pub(super) history: VecDeque<Action<T>>,
+    pub(super) redo_history: Vec<Action<T>>,
+    pub(super) tree: Option<UndoTree<T>>, // undo tree mode if exists
+    pub(super) groups: Vec<Vec<Action<T>>>, // open groups of actions, the last one is the innermost
+    pub(super) budget: Option<HistoryBudget<T>>, // history limits if set
+    pub(super) merge: Option<MergeState>, // merge policy if set
+    pub(super) ids: HistoryIds,
+    pub(super) checkpoints: IndexMap<String, NodeId>,
+    pub(super) saved: Option<NodeId>, // state of the last save point
+    pub(super) rebase: bool, // untracked mutations rebase history instead of wiping it
+    #[cfg_attr(not(feature = "journal"), allow(dead_code))]
+    pub(super) journal: JournalSlot<T, S>, // append-only journal if attached
+    pub(super) observers: Observers,
+    pub(super) meta: HistoryMeta, // metadata of history entries
 }
@@ -0,0 +1,227 @@
+use std::iter::FusedIterator;
+use std::ops::Index;
+
+use super::defines::{Action, HistoricView, ViewIter};
+use super::private::*;
+use super::storage::Storage;
+use super::vec_historic::VecHistoric;
+
+/// Place of an element of the state before a change
+pub(super) enum Found<'a, T> {
+    At(usize),     // position after the change
+    Kept(&'a T),   // the element was removed or replaced by the change and is kept by its inverse
+}
+
+/// Returns the position `p` of ascending `positions` skipped, e.g. of elements inserted between others
+fn skip_positions(p: usize, positions: impl Iterator<Item = usize>) -> usize {
+    let mut p = p;
+
+    for position in positions {
+        if position > p {
+            break;
+        }
+        p += 1;
+    }
+
+    return p;
+}
+
+/// Finds the element at `p` of the state before a change whose inverse is `action`, `len` is the length of that state
+pub(super) fn locate<T>(action: &Action<T>, p: usize, len: usize) -> Found<'_, T> {
+    match action {
+        Action::Insert(data) if p < data.index => return Found::At(p),
+        Action::Insert(data) => return Found::At(p + data.amount),
+        Action::PushBack => return Found::At(p),
+        Action::PushFront => return Found::At(p + 1),
+        // put back positions are descending
+        Action::Reinsert(indecies) => return Found::At(skip_positions(p, indecies.iter().rev().copied())),
+        Action::Remove(data) => match data.indecies.binary_search_by(|index| p.cmp(index)) {
+            Ok(k) => return Found::Kept(&data.values[k]),
+            Err(k) => return Found::At(p - (data.indecies.len() - k)),
+        },
+        Action::PopBack(value) if p + 1 == len => return Found::Kept(value),
+        Action::PopBack(_) => return Found::At(p),
+        Action::PopFront(value) if p == 0 => return Found::Kept(value),
+        Action::PopFront(_) => return Found::At(p - 1),
+        Action::Replace(data) if (data.index..data.index + data.values.len()).contains(&p) => {
+            return Found::Kept(&data.values[p - data.index]);
+        }
+        Action::Replace(_) => return Found::At(p),
+        Action::Permute(data) if (data.index..data.index + data.order.len()).contains(&p) => {
+            return Found::At(data.index + data.order[p - data.index]);
+        }
+        Action::Permute(_) => return Found::At(p),
+        // the change gathered elements of `indecies` at `dest_index`
+        Action::Move(data) => match data.indecies.binary_search(&p) {
+            Ok(k) => return Found::At(data.dest_index + k),
+            Err(k) if p - k < data.dest_index => return Found::At(p - k),
+            Err(k) => return Found::At(p - k + data.indecies.len()),
+        },
+        // the change spread elements from `dest_index` to `indecies`
+        Action::MoveBack(data) => {
+            let amount = data.indecies.len();
+
+            if (data.dest_index..data.dest_index + amount).contains(&p) {
+                return Found::At(data.indecies[p - data.dest_index]);
+            }
+
+            let rank = if p < data.dest_index { p } else { p - amount };
+
+            return Found::At(skip_positions(rank, data.indecies.iter().copied()));
+        }
+        Action::Group(actions) => {
+            let mut p = p;
+            let mut len = len;
+
+            for action in actions {
+                match locate(action, p, len) {
+                    Found::At(next) => p = next,
+                    kept => return kept,
+                }
+
+                len = (len as isize + action_len_delta(action)) as usize;
+            }
+
+            return Found::At(p);
+        }
+        Action::Custom(_) | Action::CustomBack(_) => unreachable!(),
+    }
+}
+
+/// Returns true if the action holds a command, positions of its elements are unknown
+pub(super) fn has_command<T>(action: &Action<T>) -> bool {
+    match action {
+        Action::Custom(_) | Action::CustomBack(_) => return true,
+        Action::Group(actions) => return actions.iter().any(has_command),
+        _ => return false,
+    }
+}
+
+impl<T, S: Storage<T>> VecHistoric<T, S> {
+    /// Returns a read-only view of the state before the last `steps_back` entries of history.
+    /// Elements are looked up lazily through the stored actions, an access walks all `steps_back` of them
+    /// Returns None if history has fewer entries or one of them holds a custom command
+    pub fn version_view(&self, steps_back: usize) -> Option<HistoricView<'_, T, S>> {
+        let first = self.history.len().checked_sub(steps_back)?;
+
+        let mut len = self.data.len();
+        let mut steps: Vec<(&Action<T>, usize)> = Vec::with_capacity(steps_back);
+
+        for action in self.history.range(first..).rev() {
+            if has_command(action) {
+                return None;
+            }
+
+            len = (len as isize - action_len_delta(action)) as usize;
+            steps.push((action, len));
+        }
+
+        steps.reverse();
+
+        return Some(HistoricView {
+            data: &self.data,
+            steps,
+            len,
+        });
+    }
+}
+
+impl<'a, T, S: Storage<T>> HistoricView<'a, T, S> {
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index` of the viewed state
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut p = index;
+
+        for &(action, len) in self.steps.iter() {
+            match locate(action, p, len) {
+                Found::At(next) => p = next,
+                Found::Kept(value) => return Some(value),
+            }
+        }
+
+        return Some(&self.data[p]);
+    }
+
+    /// Returns the count of history entries the view goes back
+    #[inline(always)]
+    pub fn steps_back(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> ViewIter<'_, 'a, T, S> {
+        ViewIter {
+            view: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+}
+
+impl<T, S: Storage<T>> Index<usize> for HistoricView<'_, T, S> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        return self.get(index).expect("index out of bounds");
+    }
+}
+
+impl<'v, 'a, T, S: Storage<T>> IntoIterator for &'v HistoricView<'a, T, S> {
+    type Item = &'a T;
+    type IntoIter = ViewIter<'v, 'a, T, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, S: Storage<T>> Iterator for ViewIter<'_, 'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.front += 1;
+        return self.view.get(self.front - 1);
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for ViewIter<'_, '_, T, S> {}
+impl<T, S: Storage<T>> FusedIterator for ViewIter<'_, '_, T, S> {}
+
+impl<'a, T, S: Storage<T>> DoubleEndedIterator for ViewIter<'_, 'a, T, S> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        return self.view.get(self.back);
+    }
+}
+
+impl<T: std::fmt::Debug, S: Storage<T>> std::fmt::Debug for HistoricView<'_, T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.debug_list().entries(self.iter()).finish();
+    }
+}
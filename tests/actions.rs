@@ -0,0 +1,134 @@
+mod common;
+
+use common::content;
+use vec_historic::{Action, InsertData, MoveData, PermuteData, RemoveData, ReplaceData, VecHistoric, VecHistoricError, vec_historic};
+
+#[test]
+fn applied_inverse_brings_collection_back() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5];
+    let moved = Action::MoveBack(MoveData {
+        dest_index: 0,
+        indecies: vec![2, 4],
+    });
+
+    let inverse = b.apply_action(moved).unwrap();
+    assert_eq!(content(&b), [2, 4, 0, 1, 3, 5]);
+
+    let again = b.apply_action(inverse).unwrap();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4, 5]);
+
+    b.apply_action(again).unwrap();
+    assert_eq!(content(&b), [2, 4, 0, 1, 3, 5]);
+}
+
+#[test]
+fn recorded_actions_replay_on_another_collection() {
+    let mut a: VecHistoric<i32> = vec_historic![0, 1, 2];
+    let mut b = a.clone();
+
+    a.insert_many_historic(1, [10, 11]);
+    a.set_historic(0, 20);
+    a.pop_front_historic();
+
+    // history keeps inverses of the changes, their inverses are the changes themselves
+    let mut undo = a.clone();
+    let history: Vec<Action<i32>> = a.iter_history().map(|entry| entry.action.clone()).collect();
+    let changes: Vec<Action<i32>> = history
+        .into_iter()
+        .rev()
+        .map(|action| undo.apply_action(action).unwrap())
+        .collect();
+
+    assert_eq!(content(&undo), [0, 1, 2]);
+
+    for change in changes.into_iter().rev() {
+        b.apply_action(change).unwrap();
+    }
+
+    assert_eq!(content(&b), content(&a));
+}
+
+#[test]
+fn invalid_action_is_rejected() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    b.push_back_historic(3);
+
+    let insert = Action::Insert(InsertData { index: 2, amount: 3 });
+    assert_eq!(b.apply_action(insert).err(), Some(VecHistoricError::InvalidAction));
+    assert!(b.apply_action_historic(Action::Reinsert(vec![1, 4])).is_err());
+
+    assert_eq!(content(&b), [0, 1, 2, 3]);
+    assert_eq!(b.len_history(), 1);
+}
+
+#[test]
+fn apply_rebases_history_in_rebase_mode() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+    b.set_rebase_history(true);
+    b.push_back_historic(4);
+    b.set_historic(1, 10);
+
+    let put_back = Action::Remove(RemoveData { indecies: vec![0], values: vec![-1] });
+    b.apply_action(put_back).unwrap();
+    b.apply_action(Action::Permute(PermuteData { index: 3, order: vec![1, 0] })).unwrap();
+    assert_eq!(content(&b), [-1, 0, 10, 3, 2, 4]);
+    assert_eq!(b.len_history(), 2);
+
+    // the replaced element is no longer the pushed one, undo keeps it
+    b.apply_action(Action::Replace(ReplaceData { index: 5, values: vec![5] })).unwrap();
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 3, 2, 5]);
+}
+
+#[test]
+fn apply_wipes_history_without_rebase_mode() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+    b.push_back_historic(4);
+
+    b.apply_action(Action::PushFront).unwrap();
+    assert_eq!(content(&b), [1, 2, 3, 4]);
+    assert_eq!(b.len_history(), 0);
+}
+
+#[test]
+fn historic_apply_is_undone() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+
+    b.apply_action_historic(Action::PopFront(-1)).unwrap();
+    b.apply_action_historic(Action::Insert(InsertData { index: 1, amount: 2 })).unwrap();
+    assert_eq!(content(&b), [-1, 2, 3]);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3]);
+
+    b.redo();
+    assert_eq!(content(&b), [-1, 0, 1, 2, 3]);
+}
+
+#[test]
+fn describe_tells_what_applying_does() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+    b.insert_many_historic(1, [10, 11]);
+    b.replace_range_historic(0..3, [7, 8, 9]);
+    b.begin_group();
+    b.push_back_historic(4);
+    b.pop_front_historic();
+    b.commit_group();
+
+    let history: Vec<String> = b.iter_history().map(|entry| entry.describe()).collect();
+    assert_eq!(
+        history,
+        ["remove 2 elements at 1..3", "replace 3 elements at 0..3", "group of 2 actions"]
+    );
+
+    b.undo();
+    assert_eq!(b.iter_redo().last().unwrap().describe(), "group of 2 actions");
+
+    b.undo();
+    assert_eq!(b.iter_redo().last().unwrap().describe(), "replace 3 elements at 0..3");
+
+    b.undo();
+    assert_eq!(b.iter_redo().last().unwrap().describe(), "insert 2 elements");
+}
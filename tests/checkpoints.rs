@@ -0,0 +1,132 @@
+mod common;
+
+use common::content;
+use vec_historic::{HistoryLimits, VecHistoric, vec_historic};
+
+#[test]
+fn undo_to_rolls_back_to_a_checkpoint() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    b.push_back_historic(2);
+    b.checkpoint("two");
+    b.push_back_historic(3);
+    b.push_back_historic(4);
+
+    assert!(b.undo_to("two"));
+    assert_eq!(content(&b), [1, 2]);
+    assert_eq!(b.len_redo(), 2);
+
+    // a checkpoint ahead is reached by redo
+    b.redo();
+    b.redo();
+    b.checkpoint("four");
+    b.undo_to("two");
+    assert!(b.undo_to("four"));
+    assert_eq!(content(&b), [1, 2, 3, 4]);
+
+    assert!(!b.undo_to("missing"));
+}
+
+#[test]
+fn checkpoint_of_the_same_name_is_moved() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    let first = b.checkpoint("mark");
+    b.push_back_historic(2);
+    let second = b.checkpoint("mark");
+
+    assert_ne!(first, second);
+    b.push_back_historic(3);
+    b.undo_to("mark");
+    assert_eq!(content(&b), [1, 2]);
+
+    assert!(b.remove_checkpoint("mark"));
+    assert!(!b.remove_checkpoint("mark"));
+    assert!(!b.is_checkpoint_valid("mark"));
+}
+
+#[test]
+fn checkpoints_report_invalidation() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    b.checkpoint("start");
+    b.push_back_historic(2);
+    b.checkpoint("pushed");
+    b.push_back_historic(3);
+    b.checkpoint("last");
+    assert!(b.invalid_checkpoints().is_empty());
+
+    // a new action after undo drops the undone state in linear mode
+    b.undo();
+    b.push_front_historic(0);
+    assert_eq!(b.invalid_checkpoints(), ["last"]);
+    assert!(!b.undo_to("last"));
+
+    // an untracked mutation wipes history
+    b.insert(0, -1);
+    assert_eq!(b.invalid_checkpoints(), ["start", "pushed", "last"]);
+    assert!(!b.undo_to("start"));
+}
+
+#[test]
+fn history_limits_and_clear_history_invalidate_checkpoints() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    b.checkpoint("start");
+    b.set_history_limits(HistoryLimits {
+        max_actions: Some(1),
+        ..Default::default()
+    });
+
+    b.push_back_historic(2);
+    b.checkpoint("two");
+    assert!(b.is_checkpoint_valid("start"));
+
+    b.push_back_historic(3);
+    assert!(!b.is_checkpoint_valid("start"));
+    assert!(b.is_checkpoint_valid("two"));
+
+    b.clear_history();
+    assert!(!b.is_checkpoint_valid("two"));
+}
+
+#[test]
+fn dirty_state_follows_the_save_point() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    assert!(!b.is_dirty());
+
+    b.push_back_historic(2);
+    assert!(b.is_dirty());
+    b.mark_saved();
+    assert!(!b.is_dirty());
+
+    b.push_back_historic(3);
+    assert!(b.is_dirty());
+    b.undo();
+    assert!(!b.is_dirty());
+    b.undo();
+    assert!(b.is_dirty());
+    b.redo();
+    assert!(!b.is_dirty());
+
+    // actions of an open group make the collection dirty before commit
+    b.begin_group();
+    b.push_front_historic(0);
+    assert!(b.is_dirty());
+    b.abort_group();
+    assert!(!b.is_dirty());
+
+    b.push_back(4);
+    assert!(b.is_dirty());
+}
+
+#[test]
+fn undo_to_is_refused_inside_a_group() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    b.checkpoint("start");
+    b.push_back_historic(2);
+
+    b.begin_group();
+    b.push_back_historic(3);
+    assert!(!b.undo_to("start"));
+    b.commit_group();
+
+    assert!(b.undo_to("start"));
+    assert_eq!(content(&b), [1]);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{AddAll, Negate, content};
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+#[test]
+fn command_is_undone_and_redone() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert!(!b.apply_command_historic(Negate { index: 1 }));
+    b.push_back_historic(4);
+    assert!(!b.apply_command_historic(Negate { index: 3 }));
+    assert_eq!(content(&b), [1, -2, 3, -4]);
+
+    b.undo();
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    b.redo();
+    assert_eq!(content(&b), [1, -2, 3]);
+    assert_eq!(b.iter_redo().last().unwrap().describe(), "push back element");
+    assert_eq!(b.iter_history().last().unwrap().describe(), "revert command");
+}
+
+#[test]
+fn consecutive_commands_are_merged() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert!(!b.apply_command_historic(AddAll { amount: 1 }));
+    assert!(b.apply_command_historic(AddAll { amount: 10 }));
+    assert!(!b.apply_command_historic(Negate { index: 0 }));
+    assert_eq!(content(&b), [-12, 13, 14]);
+    assert_eq!(b.len_history(), 2);
+    assert_eq!(b.iter_history().next().unwrap().describe(), "revert add 11");
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    // a command isn't merged into one which can be redone
+    b.redo();
+    assert!(!b.apply_command_historic(AddAll { amount: 1 }));
+    assert_eq!(b.len_history(), 2);
+}
+
+#[test]
+fn merge_changes_the_current_state() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    b.apply_command_historic(AddAll { amount: 1 });
+    b.mark_saved();
+    b.checkpoint("added");
+
+    b.apply_command_historic(AddAll { amount: 1 });
+    assert!(b.is_dirty());
+    assert!(!b.undo_to("added"));
+}
+
+#[test]
+fn commands_are_grouped() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    let result: Result<(), ()> = b.transaction(|b| {
+        b.apply_command_historic(AddAll { amount: 5 });
+        b.pop_front_historic();
+        b.apply_command_historic(Negate { index: 0 });
+        Ok(())
+    });
+
+    assert!(result.is_ok());
+    assert_eq!(content(&b), [-7, 8]);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    b.begin_group();
+    b.apply_command_historic(AddAll { amount: 5 });
+    b.abort_group();
+    assert_eq!(content(&b), [1, 2, 3]);
+}
+
+#[test]
+fn custom_action_is_applied_like_others() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    b.apply_action_historic(Action::CustomBack(Box::new(AddAll { amount: 2 }))).unwrap();
+    assert_eq!(content(&b), [3, 4, 5]);
+
+    // commands are cloned with the history
+    let mut cloned = b.clone();
+    cloned.undo();
+    assert_eq!(content(&cloned), [1, 2, 3]);
+    assert_eq!(content(&b), [3, 4, 5]);
+}
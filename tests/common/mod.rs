@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests, each test crate uses a part of them
+#![allow(dead_code)]
+
+use vec_historic::{Elements, HistoricCommand, Storage, VecHistoric, vec_historic};
+
+/// Returns elements of the collection
+pub fn content<S: Storage<i32>>(b: &VecHistoric<i32, S>) -> Vec<i32> {
+    b.get_inner_data().iter().copied().collect()
+}
+
+/// Collection of `0..10` with the elements at `selects` selected in that order
+pub fn sample(selects: &[usize]) -> VecHistoric<i32> {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    for &index in selects {
+        b.select(index);
+    }
+
+    b
+}
+
+/// Adds `amount` to every element, merges with the next `AddAll`
+#[derive(Clone, Debug)]
+pub struct AddAll {
+    pub amount: i32,
+}
+
+impl HistoricCommand<i32> for AddAll {
+    fn apply(&mut self, data: &mut dyn Elements<i32>) {
+        data.iter_mut().for_each(|value| *value += self.amount);
+    }
+
+    fn revert(&mut self, data: &mut dyn Elements<i32>) {
+        data.iter_mut().for_each(|value| *value -= self.amount);
+    }
+
+    fn merge_with(&mut self, next: &dyn HistoricCommand<i32>) -> bool {
+        let Some(next) = next.as_any().downcast_ref::<AddAll>() else {
+            return false;
+        };
+
+        self.amount += next.amount;
+        true
+    }
+
+    fn describe(&self) -> String {
+        format!("add {}", self.amount)
+    }
+}
+
+/// Negates the element at `index`
+#[derive(Clone, Debug)]
+pub struct Negate {
+    pub index: usize,
+}
+
+impl HistoricCommand<i32> for Negate {
+    fn apply(&mut self, data: &mut dyn Elements<i32>) {
+        data[self.index] = -data[self.index];
+    }
+
+    fn revert(&mut self, data: &mut dyn Elements<i32>) {
+        self.apply(data);
+    }
+
+    fn affected_range(&self, _len: usize) -> std::ops::Range<usize> {
+        self.index..self.index + 1
+    }
+}
+
+/// Doubles every element
+#[derive(Clone, Debug)]
+pub struct Double;
+
+impl HistoricCommand<i32> for Double {
+    fn apply(&mut self, data: &mut dyn Elements<i32>) {
+        data.iter_mut().for_each(|value| *value *= 2);
+    }
+
+    fn revert(&mut self, data: &mut dyn Elements<i32>) {
+        for index in 0..data.len() {
+            data[index] /= 2;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::ops::Bound;
+
+use common::content;
+use vec_historic::{Action, MoveData, PermuteData, ReplaceData, VecHistoric, VecHistoricError, vec_historic};
+
+#[test]
+fn out_of_bounds_indecies_leave_collection_unchanged() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert_eq!(b.try_insert(4, 0), Err(VecHistoricError::IndexOutOfBounds { index: 4, len: 3 }));
+    assert_eq!(b.try_remove(3), Err(VecHistoricError::IndexOutOfBounds { index: 3, len: 3 }));
+    assert_eq!(b.try_select(3), Err(VecHistoricError::IndexOutOfBounds { index: 3, len: 3 }));
+    assert!(b.try_insert_many_historic(5, [0, 0]).is_err());
+    assert!(b.try_set_historic(3, 0).is_err());
+
+    assert_eq!(content(&b), [1, 2, 3]);
+    assert_eq!(b.len_selects(), 0);
+    assert_eq!(b.len_history(), 0);
+
+    assert_eq!(b.try_remove(0), Ok(1));
+    assert_eq!(b.try_insert_historic(2, 4), Ok(()));
+    assert_eq!(content(&b), [2, 3, 4]);
+}
+
+#[test]
+fn ranges_are_checked() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert!(matches!(b.try_drain(2..5), Err(VecHistoricError::RangeOutOfBounds { start: 2, end: 5, len: 3 })));
+    assert_eq!(
+        b.try_replace_range_historic(0..2, [7]),
+        Err(VecHistoricError::LengthMismatch { expected: 2, found: 1 })
+    );
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    assert_eq!(b.try_replace_range_historic(0..2, [7, 8]).map(<[i32]>::to_vec), Ok(vec![1, 2]));
+    assert_eq!(content(&b), [7, 8, 3]);
+}
+
+#[test]
+fn overflowing_bounds_are_errors() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    let error = VecHistoricError::RangeOutOfBounds { start: 0, end: usize::MAX, len: 3 };
+    assert!(matches!(b.try_drain(..=usize::MAX), Err(found) if found == error));
+    assert!(b.try_drain((Bound::Excluded(usize::MAX), Bound::Unbounded)).is_err());
+
+    let actions = [
+        Action::Replace(ReplaceData { index: usize::MAX, values: vec![0] }),
+        Action::Move(MoveData { dest_index: usize::MAX, indecies: vec![0] }),
+        Action::MoveBack(MoveData { dest_index: usize::MAX, indecies: vec![0] }),
+        Action::Permute(PermuteData { index: usize::MAX, order: vec![1, 0] }),
+    ];
+
+    for action in actions {
+        assert_eq!(b.apply_action(action).err(), Some(VecHistoricError::InvalidAction));
+    }
+
+    assert_eq!(content(&b), [1, 2, 3]);
+}
+
+#[test]
+fn undo_and_redo_report_empty_sequences() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert_eq!(b.try_undo().map(|entry| entry.values()), Err(VecHistoricError::NothingToUndo));
+    assert_eq!(b.try_redo().map(|entry| entry.values()), Err(VecHistoricError::NothingToRedo));
+
+    b.push_back_historic(4);
+    b.begin_group();
+    assert_eq!(b.try_undo().map(|entry| entry.values()), Err(VecHistoricError::GroupOpen));
+    b.commit_group();
+
+    assert_eq!(b.try_undo().map(|entry| entry.values()), Ok(vec![&4]));
+    assert_eq!(b.try_redo().map(|entry| entry.values()), Ok(vec![]));
+}
+
+#[test]
+fn stale_selects_are_reported() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.select(1);
+    b.select(7);
+
+    assert!(b.try_get_selected().is_err());
+    assert!(b.try_remove_selects_historic().is_err());
+    assert!(b.try_move_selects_historic(0).is_err());
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    b.deselect(7);
+    assert_eq!(b.try_move_selects_historic(0), Ok(()));
+    assert_eq!(content(&b), [2, 1, 3]);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use common::content;
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+#[test]
+fn group_is_undone_as_one_step() {
+    let mut b: VecHistoric<i32> = (0..6).collect();
+
+    // a drag and drop: the selects are removed and put back elsewhere
+    b.begin_group();
+    b.select(1);
+    b.select(2);
+    let dragged = b.remove_selects_historic().clone();
+    b.insert_many_historic(3, dragged.into_iter().rev());
+    assert!(b.commit_group());
+
+    assert_eq!(content(&b), [0, 3, 4, 1, 2, 5]);
+    assert_eq!(b.len_history(), 1);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::Group(_)));
+
+    b.undo();
+    assert_eq!(content(&b), (0..6).collect::<Vec<_>>());
+    b.redo();
+    assert_eq!(content(&b), [0, 3, 4, 1, 2, 5]);
+}
+
+#[test]
+fn nested_groups_join_the_outer_one() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+
+    b.begin_group();
+    b.push_back_historic(2);
+    b.begin_group();
+    b.push_back_historic(3);
+    b.push_front_historic(0);
+    assert!(b.commit_group());
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(b.len_groups(), 1);
+    assert!(b.commit_group());
+    assert!(!b.commit_group());
+
+    assert_eq!(b.len_history(), 1);
+    b.undo();
+    assert_eq!(content(&b), [1]);
+}
+
+#[test]
+fn undo_is_refused_while_a_group_is_open() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    b.push_back_historic(2);
+
+    b.begin_group();
+    b.push_back_historic(3);
+    assert!(b.undo().is_none());
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    b.commit_group();
+    assert!(b.undo().is_some());
+    assert_eq!(content(&b), [1, 2]);
+}
+
+#[test]
+fn empty_or_single_groups_make_plain_entries() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+
+    b.begin_group();
+    b.commit_group();
+    assert_eq!(b.len_history(), 0);
+
+    b.begin_group();
+    b.push_back_historic(2);
+    b.commit_group();
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::PushBack));
+}
+
+#[test]
+fn abort_rolls_the_group_back() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.push_back_historic(4);
+
+    b.begin_group();
+    b.push_back_historic(10);
+    b.insert_historic(1, 20);
+    // the rollback erases the inserted elements, the last one first
+    assert_eq!(b.abort_group(), [20, 10]);
+
+    assert_eq!(content(&b), [1, 2, 3, 4]);
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.len_groups(), 0);
+}
+
+#[test]
+fn transaction_commits_on_ok_and_rolls_back_on_err() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    let result: Result<usize, ()> = b.transaction(|b| {
+        b.push_back_historic(4);
+        b.pop_front_historic();
+        Ok(b.len())
+    });
+    assert_eq!(result, Ok(3));
+    assert_eq!(content(&b), [2, 3, 4]);
+    assert_eq!(b.len_history(), 1);
+
+    let result: Result<(), &str> = b.transaction(|b| {
+        b.push_back_historic(5);
+        // a group left open is closed with the transaction
+        b.begin_group();
+        b.push_front_historic(20);
+        Err("invalid")
+    });
+    assert_eq!(result, Err("invalid"));
+    assert_eq!(content(&b), [2, 3, 4]);
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.len_groups(), 0);
+}
+
+#[test]
+fn transaction_rolls_back_on_panic() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.push_back_historic(4);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _: Result<(), ()> = b.transaction(|b| {
+            b.insert_many_historic(0, [-2, -1]);
+            b.select(0);
+            b.remove_selects_historic();
+            panic!("editor crashed");
+        });
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(content(&b), [1, 2, 3, 4]);
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.len_groups(), 0);
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+}
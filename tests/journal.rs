@@ -0,0 +1,291 @@
+#![cfg(feature = "journal")]
+
+mod common;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use common::{Negate, content};
+use vec_historic::{JournalOptions, MergePolicy, VecHistoric, vec_historic};
+
+fn journal_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vec_historic_{}_{name}.journal", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// Undoes everything in both collections checking they stay equal
+fn assert_same_history(a: &mut VecHistoric<i32>, b: &mut VecHistoric<i32>) {
+    assert_eq!(content(a), content(b));
+    assert_eq!(a.len_history(), b.len_history());
+    assert_eq!(a.len_redo(), b.len_redo());
+
+    while a.len_history() > 0 {
+        a.undo();
+        b.undo();
+        assert_eq!(content(a), content(b));
+    }
+}
+
+#[test]
+fn recover_replays_actions_undo_redo_and_untracked_edits() {
+    let path = journal_path("replay");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3, 4, 5];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+
+    b.select(1);
+    b.select(3);
+    b.remove_selects_historic();
+    b.insert_many_historic(1, [10, 11]);
+    b.set_historic(0, 100);
+    b.undo();
+    b.push_back(6);
+    b.redo();
+    b.select(0);
+    b.move_selects_historic(3);
+    b.begin_group();
+    b.push_front_historic(-1);
+    b.pop_back_historic();
+    b.commit_group();
+    b.undo();
+    b.remove(1);
+    b.insert(0, 7);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert!(b.take_journal_error().is_none());
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recover_keeps_history_rebased_through_untracked_edits() {
+    let path = journal_path("rebase");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3, 4, 5];
+    b.set_rebase_history(true);
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+
+    b.insert_historic(2, 20);
+    b.pop_front();
+    b.drain(1..3).for_each(drop);
+    b.push_front(0);
+    b.set_historic(3, 40);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert!(recovered.is_rebase_history());
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn torn_tail_is_truncated() {
+    let path = journal_path("torn");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+    b.push_back_historic(4);
+    b.push_back_historic(5);
+    b.close_journal();
+
+    let len = fs::metadata(&path).unwrap().len();
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"0badc0de {\"Do\":{\"Ins").unwrap();
+    drop(file);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(fs::metadata(&path).unwrap().len(), len);
+    assert_eq!(content(&recovered), [1, 2, 3, 4, 5]);
+
+    recovered.push_back_historic(6);
+
+    let recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(content(&recovered), [1, 2, 3, 4, 5, 6]);
+    assert_eq!(recovered.len_history(), 3);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn corrupted_record_stops_replay() {
+    let path = journal_path("corrupted");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+    b.push_back_historic(4);
+    b.push_back_historic(5);
+    b.close_journal();
+
+    // flipping a digit of the second record breaks its checksum
+    let text = fs::read_to_string(&path).unwrap();
+    let second = text.lines().nth(1).unwrap();
+    fs::write(&path, text.replace(second, &second.replace('4', "7"))).unwrap();
+
+    let recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(content(&recovered), [1, 2, 3]);
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn compaction_bounds_the_journal() {
+    let path = journal_path("compaction");
+    let mut b: VecHistoric<i32> = VecHistoric::new();
+    let options = JournalOptions {
+        compact_every: Some(4),
+        sync: false,
+    };
+    b.open_journal(&path, options).unwrap();
+
+    for i in 0..21 {
+        b.push_back_historic(i);
+    }
+
+    assert!(fs::read_to_string(&path).unwrap().lines().count() <= 5);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn unjournaled_change_is_snapshotted_by_the_next_record() {
+    let path = journal_path("stale");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+    b.push_back_historic(4);
+
+    b[0] = 10;
+    b.select(2);
+    b.move_selects(0);
+    b.push_front_historic(0);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recover_fails_without_snapshot() {
+    let path = journal_path("missing");
+    assert!(VecHistoric::<i32>::recover(&path).is_err());
+
+    fs::write(&path, "").unwrap();
+    assert!(VecHistoric::<i32>::recover(&path).is_err());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recover_replays_merged_actions() {
+    let path = journal_path("merge");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.set_merge_policy(MergePolicy::default());
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+
+    b.push_back_historic(4);
+    b.push_back_historic(5);
+    b.insert_historic(4, 6);
+    b.pop_front_historic();
+    b.pop_front_historic();
+    b.set_historic(0, 30);
+    assert_eq!(b.len_history(), 3);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_journal_stays_failed_until_compacted() {
+    let path = journal_path("failed");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+    b.push_back_historic(4);
+
+    // the command is lost, later records would be replayed over the wrong state
+    b.apply_command_historic(Negate { index: 0 });
+    assert!(b.is_journal_failed());
+    assert!(b.take_journal_error().is_some());
+    assert!(b.is_journal_failed());
+
+    let len = fs::metadata(&path).unwrap().len();
+    b.push_back_historic(9);
+    b.undo();
+    b.push_back(7);
+    assert_eq!(fs::metadata(&path).unwrap().len(), len);
+
+    let recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(content(&recovered), [1, 2, 3, 4]);
+
+    // the snapshot can't be written while the command is in history
+    assert!(b.compact_journal().is_err());
+    assert!(b.is_journal_failed());
+
+    b.clear_history();
+    b.compact_journal().unwrap();
+    assert!(!b.is_journal_failed());
+    b.insert(0, 10);
+    b.push_back_historic(5);
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(content(&recovered), [10, -1, 2, 3, 4, 7, 5]);
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recover_replays_groups_which_change_their_own_elements() {
+    let path = journal_path("group");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+
+    b.begin_group();
+    b.push_back_historic(4);
+    b.set_historic(3, 40);
+    b.insert_many_historic(0, [5, 6]);
+    b.swap_historic(0, 4);
+    b.select(1);
+    b.remove_selects_historic();
+    b.commit_group();
+    b.undo();
+    b.redo();
+
+    let mut recovered = VecHistoric::<i32>::recover(&path).unwrap();
+    assert_eq!(content(&recovered), content(&b));
+    assert_same_history(&mut b, &mut recovered);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn commands_fail_the_journal_with_a_clear_error() {
+    let path = journal_path("command");
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.open_journal(&path, JournalOptions::default()).unwrap();
+
+    b.begin_group();
+    b.push_back_historic(4);
+    b.apply_command_historic(Negate { index: 0 });
+    assert!(!b.is_journal_failed());
+    b.commit_group();
+
+    assert!(b.is_journal_failed());
+    assert!(b.take_journal_error().unwrap().to_string().contains("custom commands can't be journaled"));
+
+    let error = b.compact_journal().unwrap_err();
+    assert!(error.to_string().contains("commands can't be serialized"));
+
+    // the command is still undone and redone while the journal waits
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+    b.redo();
+    assert_eq!(content(&b), [-1, 2, 3, 4]);
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use common::content;
+use vec_historic::{HistoryLimits, VecHistoric, vec_historic};
+
+#[test]
+fn oldest_actions_are_evicted_past_max_actions() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3, 4];
+    b.pop_back_historic();
+    b.pop_back_historic();
+    b.pop_back_historic();
+
+    let evicted = b.set_history_limits(HistoryLimits {
+        max_actions: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(evicted, [4, 3]);
+    assert_eq!(b.len_history(), 1);
+
+    b.push_back_historic(5);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(content(&b), [1]);
+}
+
+#[test]
+fn evicted_values_are_kept_if_asked() {
+    let mut b: VecHistoric<i32> = (0..10).collect();
+    b.set_history_limits(HistoryLimits {
+        max_values: Some(2),
+        keep_evicted: true,
+        ..Default::default()
+    });
+
+    b.pop_front_historic();
+    b.pop_front_historic();
+    assert!(b.take_evicted().is_empty());
+
+    b.pop_front_historic();
+    b.pop_front_historic();
+    assert_eq!(b.take_evicted(), [0, 1]);
+    assert!(b.take_evicted().is_empty());
+
+    b.pop_front_historic();
+    assert_eq!(b.remove_history_limits(), [2]);
+    assert!(b.history_limits().is_none());
+}
+
+#[test]
+fn groups_are_evicted_whole() {
+    let mut b: VecHistoric<i32> = (0..10).collect();
+    b.set_history_limits(HistoryLimits {
+        max_values: Some(4),
+        keep_evicted: true,
+        ..Default::default()
+    });
+
+    // three values in one entry fit the limit
+    b.begin_group();
+    b.pop_back_historic();
+    b.pop_front_historic();
+    b.pop_front_historic();
+    b.commit_group();
+    assert_eq!(b.len_history(), 1);
+
+    // the group isn't split when the next entry exceeds the limit
+    b.select(0);
+    b.select(1);
+    b.remove_selects_historic();
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.take_evicted(), [9, 0, 1]);
+
+    b.undo();
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(content(&b), [2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn open_groups_are_evicted_after_commit() {
+    let mut b: VecHistoric<i32> = (0..10).collect();
+    b.set_history_limits(HistoryLimits {
+        max_values: Some(1),
+        ..Default::default()
+    });
+
+    b.begin_group();
+    b.pop_back_historic();
+    b.pop_back_historic();
+    assert_eq!(content(&b), (0..8).collect::<Vec<_>>());
+
+    b.commit_group();
+    assert_eq!(b.len_history(), 1);
+
+    b.push_back_historic(8);
+    assert_eq!(b.len_history(), 1);
+    b.undo();
+    assert!(b.undo().is_none());
+}
+
+#[test]
+fn bytes_are_counted_by_size_function() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 5, 3, 4];
+    b.set_history_limits(HistoryLimits {
+        max_bytes: Some(10),
+        size_of: |value| *value as usize,
+        ..Default::default()
+    });
+
+    b.pop_back_historic();
+    b.pop_back_historic();
+    assert_eq!(b.len_history(), 2);
+
+    // the popped 4 is evicted to fit 3 and 5
+    b.pop_back_historic();
+    assert_eq!(b.len_history(), 2);
+    assert_eq!(content(&b), [1]);
+}
+
+#[test]
+fn entry_over_the_limits_alone_is_kept_until_the_next_one() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 100, 3];
+    b.set_history_limits(HistoryLimits {
+        max_bytes: Some(10),
+        size_of: |value| *value as usize,
+        ..Default::default()
+    });
+
+    b.pop_back_historic();
+    assert_eq!(b.pop_back_historic(), Some(&100));
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(content(&b), [1]);
+
+    b.push_back_historic(2);
+    assert_eq!(b.len_history(), 1);
+    b.undo();
+    assert_eq!(content(&b), [1]);
+}
+
+#[test]
+fn redo_is_limited_too() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.pop_back_historic();
+    b.pop_back_historic();
+    b.undo();
+    b.undo();
+
+    // the last action to redo is evicted to fit
+    b.set_history_limits(HistoryLimits {
+        max_actions: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(b.len_redo(), 1);
+    b.redo();
+    assert!(b.redo().is_none());
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(content(&b), [1, 2]);
+}
+
+#[test]
+fn redo_is_evicted_before_history() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.pop_front_historic();
+    b.push_back_historic(4);
+    b.undo();
+
+    let evicted = b.set_history_limits(HistoryLimits {
+        max_values: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(evicted, [4]);
+    assert_eq!(b.len_redo(), 0);
+    assert_eq!(b.len_history(), 1);
+}
+
+#[test]
+fn oldest_branches_are_evicted_first() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.enable_undo_tree();
+    b.set_history_limits(HistoryLimits {
+        max_values: Some(4),
+        keep_evicted: true,
+        ..Default::default()
+    });
+
+    for i in 1..=3 {
+        b.insert_many_historic(0, [i, i]);
+        b.undo();
+    }
+    assert_eq!(b.node_children(b.current_node()).len(), 3);
+    assert!(b.take_evicted().is_empty());
+
+    b.insert_many_historic(0, [4, 4]);
+    assert_eq!(b.take_evicted(), [1, 1]);
+    assert_eq!(b.node_children(b.current_node()).len(), 0);
+    assert_eq!(b.node_children(b.root_node()).len(), 3);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use common::content;
+use vec_historic::{Action, MergePolicy, VecHistoric, vec_historic};
+
+/// Removes the element at `index` the way backspace or delete does
+fn remove_at(b: &mut VecHistoric<i32>, index: usize) {
+    b.deselect_all();
+    b.select(index);
+    b.remove_selects_historic();
+}
+
+#[test]
+fn typing_merges_into_one_insert() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 9];
+    b.set_merge_policy(MergePolicy::default());
+
+    for (index, value) in (1..4).enumerate() {
+        b.insert_historic(index + 1, value);
+    }
+
+    // a block inserted inside the previous one still extends it
+    b.insert_many_historic(2, [10, 11]);
+    assert_eq!(content(&b), [0, 1, 10, 11, 2, 3, 9]);
+    assert_eq!(b.len_history(), 1);
+    assert!(matches!(b.iter_history().next().unwrap().action, Action::Insert(data) if data.index == 1 && data.amount == 5));
+
+    // not adjacent to the typed block
+    b.push_front_historic(-1);
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [0, 9]);
+
+    b.redo();
+    assert_eq!(content(&b), [0, 1, 10, 11, 2, 3, 9]);
+}
+
+#[test]
+fn backspace_and_delete_merge_into_one_remove() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+    b.set_merge_policy(MergePolicy::default());
+
+    // backspace from the cursor at 5, then delete at the same place
+    remove_at(&mut b, 4);
+    remove_at(&mut b, 3);
+    remove_at(&mut b, 3);
+    remove_at(&mut b, 3);
+    assert_eq!(content(&b), [0, 1, 2, 7]);
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.iter_history().next().unwrap().values(), [&6, &5, &4, &3]);
+
+    // the popped element is at the cursor too
+    assert_eq!(b.pop_back_historic(), Some(&7));
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(b.pop_front_historic(), Some(&0));
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2]);
+
+    b.undo();
+    assert_eq!(content(&b), (0..8).collect::<Vec<_>>());
+}
+
+#[test]
+fn merging_stops_on_key_window_undo_and_edits() {
+    let mut b: VecHistoric<i32> = vec_historic![];
+    b.set_merge_policy(MergePolicy {
+        window: None,
+        key: Some(1),
+    });
+
+    b.push_back_historic(1);
+    b.push_back_historic(2);
+    assert_eq!(b.len_history(), 1);
+
+    // a new word
+    b.set_merge_policy(MergePolicy {
+        window: None,
+        key: Some(2),
+    });
+    b.push_back_historic(3);
+    assert_eq!(b.len_history(), 2);
+
+    // nothing merges into an entry after undo or over an untracked edit
+    b.push_back_historic(4);
+    b.undo();
+    b.push_back_historic(5);
+    assert_eq!(b.len_history(), 2);
+    b.push_back(6);
+    b.push_back_historic(7);
+    assert_eq!(b.len_history(), 3);
+
+    b.set_merge_policy(MergePolicy {
+        window: Some(Duration::from_millis(1)),
+        key: None,
+    });
+    b.push_back_historic(8);
+    thread::sleep(Duration::from_millis(20));
+    b.push_back_historic(9);
+    assert_eq!(b.len_history(), 5);
+
+    b.remove_merge_policy();
+    assert!(b.merge_policy().is_none());
+    b.push_back_historic(10);
+    assert_eq!(b.len_history(), 6);
+    assert_eq!(content(&b), [1, 2, 5, 6, 7, 8, 9, 10]);
+}
+
+#[test]
+fn group_of_one_action_is_not_merged() {
+    let mut b: VecHistoric<i32> = vec_historic![];
+    b.set_merge_policy(MergePolicy::default());
+    b.push_back_historic(1);
+    b.push_back_historic(2);
+
+    let result: Result<(), ()> = b.transaction(|b| {
+        b.push_back_historic(3);
+        Ok(())
+    });
+    assert!(result.is_ok());
+    assert_eq!(b.len_history(), 2);
+
+    // nothing merges into the group either
+    b.push_back_historic(4);
+    assert_eq!(b.len_history(), 3);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [1, 2]);
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::AddAll;
+use vec_historic::{ActionMeta, HistoryLimits, VecHistoric, vec_historic};
+
+fn labels(b: &VecHistoric<i32>) -> Vec<Option<&str>> {
+    b.iter_history().map(|entry| entry.label()).collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct Author(&'static str);
+
+#[test]
+fn labels_are_read_from_history_and_undo() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+
+    b.with_meta(ActionMeta::new().with_label("Append").with_timestamp(time))
+        .push_back_historic(3);
+    b.pop_front_historic();
+    b.with_meta(ActionMeta::new().with_label("Edit").with_user(Author("ann")))
+        .set_historic(0, 10);
+
+    assert_eq!(labels(&b), [Some("Append"), None, Some("Edit")]);
+
+    let undone = b.undo().unwrap();
+    assert_eq!(undone.label(), Some("Edit"));
+    assert_eq!(undone.meta.unwrap().user_as::<Author>(), Some(&Author("ann")));
+    assert_eq!(undone.meta.unwrap().user_as::<String>(), None);
+    assert_eq!(undone.values(), [&10]);
+
+    assert!(b.undo().unwrap().meta.is_none());
+    assert_eq!(b.undo().unwrap().meta.unwrap().timestamp, Some(time));
+    assert!(b.undo().is_none());
+
+    let redo: Vec<Option<&str>> = b.iter_redo().map(|entry| entry.label()).collect();
+    assert_eq!(redo, [Some("Edit"), None, Some("Append")]);
+    assert_eq!(b.redo().unwrap().label(), Some("Append"));
+}
+
+#[test]
+fn group_takes_metadata_of_its_entry() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+
+    b.with_meta(ActionMeta::new().with_label("Paste"));
+    let result: Result<(), ()> = b.transaction(|b| {
+        b.push_back_historic(3);
+        b.push_back_historic(4);
+        Ok(())
+    });
+
+    assert!(result.is_ok());
+    assert_eq!(labels(&b), [Some("Paste")]);
+
+    // metadata of an aborted group isn't left for the next entry
+    b.with_meta(ActionMeta::new().with_label("Cut"));
+    b.begin_group();
+    b.pop_back_historic();
+    b.abort_group();
+    b.pop_back_historic();
+
+    assert_eq!(labels(&b), [Some("Paste"), None]);
+}
+
+#[test]
+fn metadata_follows_entries() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    b.set_history_limits(HistoryLimits {
+        max_actions: Some(2),
+        ..Default::default()
+    });
+
+    for value in 3..6 {
+        b.with_meta(ActionMeta::new().with_label(format!("push {value}")).stamped())
+            .push_back_historic(value);
+    }
+
+    // untracked push_back keeps history in linear mode
+    b.push_back(6);
+    assert_eq!(labels(&b), [Some("push 4"), Some("push 5")]);
+    assert!(b.iter_history().all(|entry| entry.meta.unwrap().timestamp.is_some()));
+
+    let cloned = b.clone();
+    assert_eq!(labels(&cloned), [Some("push 4"), Some("push 5")]);
+
+    b.clear_history();
+    b.push_back_historic(7);
+    assert_eq!(labels(&b), [None]);
+}
+
+#[test]
+fn merged_command_takes_new_metadata() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+
+    b.with_meta(ActionMeta::new().with_label("Add 1"))
+        .apply_command_historic(AddAll { amount: 1 });
+    b.apply_command_historic(AddAll { amount: 1 });
+    assert_eq!(labels(&b), [Some("Add 1")]);
+
+    b.with_meta(ActionMeta::new().with_label("Add 3"))
+        .apply_command_historic(AddAll { amount: 1 });
+    assert_eq!(labels(&b), [Some("Add 3")]);
+}
+
+#[test]
+fn undo_tree_nodes_keep_metadata() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    b.enable_undo_tree();
+
+    b.with_meta(ActionMeta::new().with_label("first")).push_back_historic(3);
+    let first = b.current_node();
+    b.undo();
+
+    b.with_meta(ActionMeta::new().with_label("second")).push_back_historic(4);
+    let second = b.current_node();
+
+    assert!(b.jump_to_node(first));
+    assert_eq!(labels(&b), [Some("first")]);
+    assert_eq!(b.node_meta(second).unwrap().label.as_deref(), Some("second"));
+    assert!(b.node_meta(b.root_node()).is_none());
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{content, sample};
+
+#[test]
+fn destination_is_counted_before_removal() {
+    let mut b = sample(&[1, 2]);
+    b.move_selects_historic(5);
+    assert_eq!(content(&b), [0, 3, 4, 1, 2, 5, 6, 7, 8, 9]);
+
+    let mut b = sample(&[7, 8]);
+    b.move_selects_historic(2);
+    assert_eq!(content(&b), [0, 1, 7, 8, 2, 3, 4, 5, 6, 9]);
+}
+
+#[test]
+fn non_contiguous_selects_keep_their_order() {
+    let mut b = sample(&[8, 1, 5]);
+    b.move_selects_historic(3);
+    assert_eq!(content(&b), [0, 2, 1, 5, 8, 3, 4, 6, 7, 9]);
+    assert_eq!(b.get_selected(), [&8, &1, &5]);
+}
+
+#[test]
+fn destination_inside_selects() {
+    let mut b = sample(&[2, 4, 6]);
+    b.move_selects_historic(4);
+    assert_eq!(content(&b), [0, 1, 3, 2, 4, 6, 5, 7, 8, 9]);
+}
+
+#[test]
+fn destination_out_of_bounds_moves_to_back() {
+    let mut b = sample(&[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    b.move_selects_historic(100);
+    assert_eq!(content(&b), [9, 0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let mut b = sample(&[3]);
+    b.move_selects_historic(10);
+    assert_eq!(content(&b), [0, 1, 2, 4, 5, 6, 7, 8, 9, 3]);
+}
+
+#[test]
+fn empty_selection_does_nothing() {
+    let mut b = sample(&[]);
+    b.move_selects_historic(3);
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(content(&b), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn undo_restores_exact_positions() {
+    let mut b = sample(&[9, 0, 4, 5]);
+    b.move_selects_historic(7);
+    let moved = content(&b);
+    assert_eq!(moved, [1, 2, 3, 6, 0, 4, 5, 9, 7, 8]);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(b.get_selected(), [&9, &0, &4, &5]);
+
+    b.redo();
+    assert_eq!(content(&b), moved);
+}
+
+#[test]
+fn move_in_group_is_undone_with_it() {
+    let mut b = sample(&[1, 3]);
+    b.begin_group();
+    b.move_selects_historic(0);
+    b.push_front_historic(-1);
+    b.move_selects_historic(11);
+    b.commit_group();
+    assert_eq!(content(&b), [-1, 0, 2, 4, 5, 6, 7, 8, 9, 1, 3]);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn plain_move_keeps_selects_on_moved_elements() {
+    let mut b = sample(&[6, 2]);
+    b.push_back_historic(10);
+    b.move_selects(0);
+    assert_eq!(content(&b), [2, 6, 0, 1, 3, 4, 5, 7, 8, 9, 10]);
+    assert_eq!(b.get_selected(), [&6, &2]);
+    assert_eq!(b.len_history(), 0);
+}
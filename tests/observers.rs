@@ -0,0 +1,165 @@
+use std::sync::{Arc, Mutex};
+
+use vec_historic::{ChangeEvent, VecHistoric, vec_historic};
+
+/// Subscribes to changes of `b` and returns the received events
+fn observe(b: &mut VecHistoric<i32>) -> Arc<Mutex<Vec<ChangeEvent>>> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let sink = events.clone();
+
+    b.on_change(move |event| sink.lock().unwrap().push(event.clone()));
+
+    events
+}
+
+fn take(events: &Arc<Mutex<Vec<ChangeEvent>>>) -> Vec<ChangeEvent> {
+    std::mem::take(&mut *events.lock().unwrap())
+}
+
+#[test]
+fn untracked_methods_report_ranges() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5];
+    let events = observe(&mut b);
+
+    b.push_back(6);
+    b.insert_many(1, [10, 11]);
+    b.pop_front();
+    b.drain(2..4).for_each(drop);
+
+    assert_eq!(
+        take(&events),
+        [
+            ChangeEvent::Inserted(6..7),
+            ChangeEvent::Inserted(1..3),
+            ChangeEvent::Removed(0..1),
+            ChangeEvent::Removed(2..4),
+        ]
+    );
+}
+
+#[test]
+fn historic_methods_and_undo_redo_report_changes() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5];
+    let events = observe(&mut b);
+
+    b.set_historic(2, 20);
+    b.push_front_historic(-1);
+    assert_eq!(take(&events), [ChangeEvent::Replaced(2..3), ChangeEvent::Inserted(0..1)]);
+
+    b.undo();
+    assert_eq!(take(&events), [ChangeEvent::Removed(0..1), ChangeEvent::Undone]);
+
+    b.redo();
+    assert_eq!(take(&events), [ChangeEvent::Inserted(0..1), ChangeEvent::Redone]);
+}
+
+#[test]
+fn scattered_removal_is_reported_from_the_back() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5];
+    b.select(1);
+    b.select(2);
+    b.select(4);
+
+    let events = observe(&mut b);
+    b.remove_selects_historic();
+
+    assert_eq!(
+        take(&events),
+        [
+            ChangeEvent::Removed(4..5),
+            ChangeEvent::Removed(1..3),
+            ChangeEvent::SelectionChanged,
+        ]
+    );
+
+    b.undo();
+
+    assert_eq!(
+        take(&events),
+        [
+            ChangeEvent::Inserted(1..3),
+            ChangeEvent::Inserted(4..5),
+            ChangeEvent::SelectionChanged,
+            ChangeEvent::Undone,
+        ]
+    );
+}
+
+#[test]
+fn move_reports_positions_of_moved_elements() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4, 5];
+    b.select(1);
+    b.select(3);
+
+    let events = observe(&mut b);
+    b.move_selects_historic(6);
+    assert_eq!(
+        take(&events),
+        [ChangeEvent::Moved {
+            from: vec![1, 3],
+            to: vec![4, 5],
+        }]
+    );
+
+    b.undo();
+    assert_eq!(
+        take(&events),
+        [
+            ChangeEvent::Moved {
+                from: vec![4, 5],
+                to: vec![1, 3],
+            },
+            ChangeEvent::Undone,
+        ]
+    );
+}
+
+#[test]
+fn selection_changes_are_reported_once() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    let events = observe(&mut b);
+
+    b.select(1);
+    b.select(1);
+    b.deselect(0);
+    b.deselect(1);
+    b.deselect_all();
+    b.select_all();
+
+    assert_eq!(take(&events), vec![ChangeEvent::SelectionChanged; 3]);
+}
+
+#[test]
+fn group_is_reported_in_order_of_its_actions() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    let events = observe(&mut b);
+
+    b.begin_group();
+    b.push_back_historic(3);
+    b.pop_front_historic();
+    b.commit_group();
+    assert_eq!(take(&events), [ChangeEvent::Inserted(3..4), ChangeEvent::Removed(0..1)]);
+
+    b.undo();
+    assert_eq!(
+        take(&events),
+        [ChangeEvent::Inserted(0..1), ChangeEvent::Removed(3..4), ChangeEvent::Undone]
+    );
+}
+
+#[test]
+fn removed_observer_gets_no_events() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    let events = Arc::new(Mutex::new(0));
+    let sink = events.clone();
+
+    let id = b.on_change(move |_| *sink.lock().unwrap() += 1);
+    b.push_back(3);
+
+    assert!(b.remove_observer(id));
+    assert!(!b.remove_observer(id));
+    b.push_back(4);
+
+    assert_eq!(*events.lock().unwrap(), 1);
+    assert_eq!(b.clone().into_iter().count(), 5);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::content;
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+#[test]
+fn sort_and_reverse_are_undone_exactly() {
+    let mut b: VecHistoric<i32> = vec_historic![3, 1, 4, 1, 5, 9, 2, 6];
+    let original = content(&b);
+
+    b.select(0);
+    b.sort_historic();
+    assert_eq!(content(&b), [1, 1, 2, 3, 4, 5, 6, 9]);
+    assert_eq!(b.iter_selects().copied().collect::<Vec<_>>(), [3]);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::Permute(_)));
+
+    // sorting a sorted collection records nothing
+    b.sort_by_key_historic(|value| *value);
+    assert_eq!(b.len_history(), 1);
+
+    b.sort_by_historic(|a, c| c.cmp(a));
+    assert_eq!(content(&b), [9, 6, 5, 4, 3, 2, 1, 1]);
+    b.reverse_historic();
+    assert_eq!(content(&b), [1, 1, 2, 3, 4, 5, 6, 9]);
+
+    b.undo();
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), original);
+
+    while b.redo().is_some() {}
+    assert_eq!(content(&b), [1, 1, 2, 3, 4, 5, 6, 9]);
+}
+
+#[test]
+fn rotate_and_swap_move_elements() {
+    let mut b: VecHistoric<i32> = (0..6).collect();
+
+    b.rotate_left_historic(2);
+    assert_eq!(content(&b), [2, 3, 4, 5, 0, 1]);
+    b.rotate_right_historic(1);
+    assert_eq!(content(&b), [1, 2, 3, 4, 5, 0]);
+    b.swap_historic(4, 1);
+    assert_eq!(content(&b), [1, 5, 3, 4, 2, 0]);
+
+    // a full rotation or a swap with itself changes nothing
+    b.rotate_left_historic(6);
+    b.swap_historic(3, 3);
+    assert_eq!(b.len_history(), 3);
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3, 4, 5, 0]);
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), (0..6).collect::<Vec<_>>());
+}
+
+#[test]
+fn untracked_edits_rebase_permutations() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+    b.set_rebase_history(true);
+
+    b.swap_historic(1, 2);
+    b.push_front(-1);
+    b.push_back(4);
+    assert_eq!(content(&b), [-1, 0, 2, 1, 3, 4]);
+
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 2, 3, 4]);
+
+    // removing an element of the block drops the permutation
+    b.redo();
+    b.remove(2);
+    assert_eq!(b.len_history(), 0);
+}
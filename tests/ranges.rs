@@ -0,0 +1,115 @@
+mod common;
+
+use common::content;
+use vec_historic::{VecHistoric, vec_historic};
+
+#[test]
+fn drain_and_splice_are_single_entries() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+
+    assert_eq!(b.drain_historic(2..5), [4, 3, 2]);
+    assert_eq!(content(&b), [0, 1, 5, 6, 7]);
+    assert_eq!(b.len_history(), 1);
+
+    assert_eq!(b.splice_historic(1..=2, [10, 11, 12]), [5, 1]);
+    assert_eq!(content(&b), [0, 10, 11, 12, 6, 7]);
+    assert_eq!(b.len_history(), 2);
+
+    // an empty range records nothing
+    assert!(b.drain_historic(3..3).is_empty());
+    assert!(b.splice_historic(3..3, []).is_empty());
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 5, 6, 7]);
+    b.undo();
+    assert_eq!(content(&b), (0..8).collect::<Vec<_>>());
+
+    b.redo();
+    b.redo();
+    assert_eq!(content(&b), [0, 10, 11, 12, 6, 7]);
+}
+
+#[test]
+fn truncate_and_split_off_return_the_tail() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4];
+
+    assert!(b.truncate_historic(9).is_empty());
+    assert_eq!(b.truncate_historic(3), [4, 3]);
+    assert_eq!(b.split_off_historic(1), [2, 1]);
+    assert!(b.split_off_historic(1).is_empty());
+    assert_eq!(content(&b), [0]);
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2]);
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic]
+fn split_off_panics_past_the_end() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1];
+    b.split_off_historic(3);
+}
+
+#[test]
+fn drain_removes_the_range_when_dropped_early() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+    b.select(1);
+    b.select(3);
+    b.select(6);
+
+    let mut drain = b.drain(2..6);
+    assert_eq!(drain.len(), 4);
+    assert_eq!(drain.next(), Some(2));
+    assert_eq!(drain.next_back(), Some(5));
+    assert_eq!(drain.len(), 2);
+    drop(drain);
+
+    assert_eq!(content(&b), [0, 1, 6, 7]);
+    assert_eq!(b.iter_selects().copied().collect::<Vec<_>>(), [1, 2]);
+    assert!(b.drain(1..3).rev().eq([6, 1]));
+    assert_eq!(content(&b), [0, 7]);
+}
+
+#[test]
+fn drain_iter_historic_records_the_whole_range() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+    b.select(6);
+
+    let taken: Vec<i32> = b.drain_iter_historic(2..6).take(2).collect();
+    assert_eq!(taken, [2, 3]);
+    assert_eq!(content(&b), [0, 1, 6, 7]);
+    assert_eq!(b.get_selected(), [&6]);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(content(&b), (0..8).collect::<Vec<_>>());
+
+    // nothing is recorded for an empty range
+    b.drain_iter_historic(3..3).for_each(drop);
+    assert_eq!(b.len_redo(), 1);
+}
+
+#[test]
+fn leaked_drain_keeps_history_in_sync() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+    b.set_rebase_history(true);
+    b.set_historic(6, 60);
+    b.select(7);
+
+    std::mem::forget(b.drain(1..4));
+    assert_eq!(content(&b), [0, 4, 5, 60, 7]);
+    assert_eq!(b.get_selected(), [&7]);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 4, 5, 6, 7]);
+
+    // the back of the range is taken without touching the rest of it
+    let mut drain = b.drain(..);
+    assert_eq!(drain.next_back(), Some(7));
+    assert_eq!(drain.next(), Some(0));
+    assert_eq!(drain.len(), 3);
+}
@@ -0,0 +1,302 @@
+mod common;
+
+use common::{content, sample};
+use vec_historic::{Action, HistoryLimits, VecHistoric, vec_historic};
+
+fn rebased(data: &[i32]) -> VecHistoric<i32> {
+    let mut b: VecHistoric<i32> = data.iter().copied().collect();
+    b.set_rebase_history(true);
+    b
+}
+
+#[test]
+fn insert_is_shifted_around_and_inside() {
+    let mut b = rebased(&[0, 1, 2, 3, 4, 5]);
+    b.insert_many_historic(2, [10, 11]);
+
+    b.insert(0, -1);
+    b.insert(8, -2);
+    // splits the inserted range, the element stays after undo
+    b.insert(4, -3);
+    assert_eq!(content(&b), [-1, 0, 1, 10, -3, 11, 2, 3, 4, -2, 5]);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, -3, 2, 3, 4, -2, 5]);
+    b.redo();
+    assert_eq!(content(&b), [-1, 0, 1, 10, -3, 11, 2, 3, 4, -2, 5]);
+
+    // an inserted element removed by the edit isn't removed by undo again
+    b.remove(3);
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, -3, 2, 3, 4, -2, 5]);
+}
+
+#[test]
+fn remove_and_reinsert_are_shifted() {
+    let mut b = rebased(&[0, 1, 2, 3, 4, 5]);
+    b.select(1);
+    b.select(4);
+    b.remove_selects_historic();
+    assert_eq!(content(&b), [0, 2, 3, 5]);
+
+    b.insert(2, -1);
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, -1, 3, 4, 5]);
+
+    // the redo step forgets an element removed by the edit
+    b.remove(1);
+    assert_eq!(b.len_redo(), 1);
+    b.redo();
+    assert_eq!(content(&b), [0, 2, -1, 3, 5]);
+
+    b.push_front(-2);
+    b.undo();
+    assert_eq!(content(&b), [-2, 0, 2, -1, 3, 4, 5]);
+}
+
+#[test]
+fn untracked_removal_of_selects_is_rebased() {
+    let mut b = rebased(&[1, 2, 3]);
+    b.insert_historic(3, 9);
+    b.mark_saved();
+
+    // the inserted element is selected by the historic insertion
+    b.deselect_all();
+    b.select(0);
+    assert_eq!(b.remove_selects(), [1]);
+    assert_eq!(content(&b), [2, 3, 9]);
+    assert!(b.is_dirty());
+
+    b.undo();
+    assert_eq!(content(&b), [2, 3]);
+
+    // without rebase mode the removal wipes history
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.insert_historic(3, 9);
+    b.remove_selects();
+    assert_eq!(b.len_history(), 0);
+}
+
+#[test]
+fn move_is_shifted_or_dropped() {
+    let mut b = sample(&[1, 2]);
+    b.set_rebase_history(true);
+    b.move_selects_historic(5);
+
+    b.push_front(-1);
+    b.insert(7, -2);
+    assert_eq!(content(&b), [-1, 0, 3, 4, 1, 2, 5, -2, 6, 7, 8, 9]);
+
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 2, 3, 4, 5, -2, 6, 7, 8, 9]);
+    b.redo();
+    assert_eq!(content(&b), [-1, 0, 3, 4, 1, 2, 5, -2, 6, 7, 8, 9]);
+
+    // a move back conflicts with edits of the moved range as well
+    b.undo();
+    b.insert(3, -3);
+    assert_eq!(b.len_redo(), 0);
+
+    b.move_selects_historic(0);
+    b.remove(1);
+    assert_eq!(b.len_history(), 0);
+}
+
+#[test]
+fn push_and_pop_back_keep_their_kind_at_the_back() {
+    let mut b = rebased(&[0, 1, 2]);
+    b.push_back_historic(3);
+
+    // the pushed element isn't the last one anymore
+    b.push_back(4);
+    assert!(!matches!(b.iter_history().last().unwrap().action, Action::PushBack));
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 4]);
+    b.redo();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4]);
+
+    b.pop_back_historic();
+    b.push_front(-1);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::PopBack(4)));
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 2, 3, 4]);
+
+    b.redo();
+    b.push_back(5);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::Remove(_)));
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn push_and_pop_front_keep_their_kind_at_the_front() {
+    let mut b = rebased(&[0, 1, 2]);
+    b.push_front_historic(-1);
+
+    b.push_back(3);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::PushFront));
+
+    b.push_front(-2);
+    assert!(!matches!(b.iter_history().last().unwrap().action, Action::PushFront));
+    b.undo();
+    assert_eq!(content(&b), [-2, 0, 1, 2, 3]);
+
+    b.pop_front_historic();
+    b.insert(1, 5);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::PopFront(-2)));
+    b.undo();
+    assert_eq!(content(&b), [-2, 0, 5, 1, 2, 3]);
+
+    // an element pushed in front goes after the popped one
+    b.redo();
+    b.push_front(-3);
+    assert!(matches!(b.iter_history().last().unwrap().action, Action::PopFront(-2)));
+    b.undo();
+    assert_eq!(content(&b), [-2, -3, 0, 5, 1, 2, 3]);
+}
+
+#[test]
+fn replace_is_split_by_inserts_and_trimmed_by_removes() {
+    let mut b = rebased(&[0, 1, 2, 3, 4, 5]);
+    b.set_historic(1, 10);
+    b.replace_range_historic(3..5, [30, 40]);
+
+    b.insert(4, -1);
+    assert_eq!(content(&b), [0, 10, 2, 30, -1, 40, 5]);
+    b.undo();
+    assert_eq!(content(&b), [0, 10, 2, 3, -1, 4, 5]);
+    b.redo();
+
+    // a replaced element removed by the edit is forgotten
+    b.remove(1);
+    b.remove(2);
+    assert_eq!(content(&b), [0, 2, -1, 40, 5]);
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [0, 2, -1, 4, 5]);
+}
+
+#[test]
+fn permute_is_shifted_past_the_block() {
+    let mut b = rebased(&[0, 1, 2, 3]);
+    b.swap_historic(0, 1);
+
+    b.insert(3, -1);
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, -1, 3]);
+    b.redo();
+    assert_eq!(content(&b), [1, 0, 2, -1, 3]);
+}
+
+#[test]
+fn group_is_rebased_action_by_action() {
+    let mut b = rebased(&[0, 1, 2, 3]);
+    b.begin_group();
+    b.push_back_historic(4);
+    b.set_historic(0, 10);
+    b.pop_front_historic();
+    b.commit_group();
+    assert_eq!(content(&b), [1, 2, 3, 4]);
+
+    b.insert(2, -1);
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, -1, 3]);
+    b.redo();
+    assert_eq!(content(&b), [1, 2, -1, 3, 4]);
+
+    // one conflicting action drops the whole group
+    b.begin_group();
+    b.push_back_historic(5);
+    b.select(0);
+    b.move_selects_historic(2);
+    b.commit_group();
+    b.remove(0);
+    assert_eq!(b.len_history(), 0);
+}
+
+#[test]
+fn conflicting_entry_drops_older_history() {
+    let mut b = rebased(&[0, 1, 2, 3, 4, 5]);
+    b.push_front_historic(-1);
+    b.select(3);
+    b.move_selects_historic(5);
+    b.push_back_historic(6);
+    assert_eq!(content(&b), [-1, 0, 1, 3, 2, 4, 5, 6]);
+
+    b.remove(4);
+    assert_eq!(b.len_history(), 1);
+    assert_eq!(content(&b), [-1, 0, 1, 3, 4, 5, 6]);
+
+    b.undo();
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(content(&b), [-1, 0, 1, 3, 4, 5]);
+}
+
+#[test]
+fn elements_of_dropped_entries_are_evicted() {
+    let mut b = rebased(&[0, 1, 2, 3, 4]);
+    b.set_history_limits(HistoryLimits {
+        keep_evicted: true,
+        ..Default::default()
+    });
+    b.pop_front_historic();
+
+    b.begin_group();
+    b.pop_back_historic();
+    b.select(0);
+    b.move_selects_historic(2);
+    b.commit_group();
+
+    // the conflicting group is dropped first, then the older entries
+    b.remove(0);
+    assert_eq!(b.len_history(), 0);
+    assert_eq!(b.take_evicted(), [4, 0]);
+}
+
+#[test]
+fn changed_states_lose_save_point_and_checkpoints() {
+    let mut b = rebased(&[1, 2, 3]);
+    b.mark_saved();
+    b.checkpoint("start");
+    b.push_back_historic(4);
+
+    b.insert(0, 100);
+    b.undo();
+    assert_eq!(content(&b), [100, 1, 2, 3]);
+    assert!(b.is_dirty());
+    assert!(!b.is_checkpoint_valid("start"));
+
+    // a state the edit doesn't reach keeps its checkpoint
+    let mut b = rebased(&[1, 2, 3]);
+    b.checkpoint("start");
+    b.insert_historic(3, 9);
+    b.remove(3);
+    assert!(b.is_checkpoint_valid("start"));
+    assert!(b.undo_to("start"));
+    assert_eq!(content(&b), [1, 2, 3]);
+}
+
+#[test]
+fn history_is_wiped_without_rebase_mode() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    assert!(!b.is_rebase_history());
+    b.push_back_historic(3);
+
+    // pushing at the back shifts nothing and keeps history
+    b.push_back(4);
+    assert_eq!(b.len_history(), 1);
+    b.push_front(-1);
+    assert_eq!(b.len_history(), 0);
+
+    // an open group wipes history in rebase mode too
+    let mut b = rebased(&[0, 1, 2]);
+    b.push_back_historic(3);
+    b.begin_group();
+    b.push_back_historic(4);
+    b.push_front(-1);
+    b.commit_group();
+    assert_eq!(b.len_history(), 0);
+}
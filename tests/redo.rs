@@ -0,0 +1,113 @@
+mod common;
+
+use common::content;
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+/// Undoes every entry and redoes them back checking each state on the way
+fn assert_round_trip(b: &mut VecHistoric<i32>) {
+    let mut states = vec![content(b)];
+
+    while b.undo().is_some() {
+        states.push(content(b));
+    }
+
+    states.pop();
+
+    while b.redo().is_some() {
+        assert_eq!(content(b), states.pop().unwrap());
+    }
+
+    assert!(states.is_empty());
+}
+
+#[test]
+fn redo_brings_back_every_action() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3, 4, 5, 6];
+
+    b.push_back_historic(7);
+    b.push_front_historic(0);
+    b.pop_back_historic();
+    b.pop_front_historic();
+    b.insert_historic(2, 10);
+    b.insert_many_historic(4, [11, 12]);
+    b.set_historic(0, 100);
+    b.select(1);
+    b.select(5);
+    b.remove_selects_historic();
+    b.select(0);
+    b.select(3);
+    b.move_selects_historic(4);
+    b.sort_historic();
+    b.swap_historic(0, 2);
+    assert_eq!(b.len_history(), 11);
+
+    assert_round_trip(&mut b);
+    assert_eq!(b.len_redo(), 0);
+}
+
+#[test]
+fn redo_returns_the_redone_entry() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.pop_back_historic();
+    b.insert_historic(0, 9);
+
+    // undoing the insertion erases the element, the redo entry keeps it
+    let entry = b.undo().unwrap();
+    assert!(matches!(entry.action, Action::Remove(_)));
+    assert_eq!(entry.values(), [&9]);
+
+    let entry = b.redo().unwrap();
+    assert!(matches!(entry.action, Action::Insert(_)));
+    assert!(entry.values().is_empty());
+
+    b.undo();
+    let entry = b.undo().unwrap();
+    assert!(matches!(entry.action, Action::PushBack));
+    assert_eq!(b.len_redo(), 2);
+
+    let entry = b.redo().unwrap();
+    assert_eq!(entry.values(), [&3]);
+    assert_eq!(content(&b), [1, 2]);
+}
+
+#[test]
+fn new_action_clears_the_redo_sequence() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.push_back_historic(4);
+    b.push_back_historic(5);
+    b.undo();
+    b.undo();
+    assert_eq!(b.len_redo(), 2);
+
+    b.push_front_historic(0);
+    assert_eq!(b.len_redo(), 0);
+    assert!(b.redo().is_none());
+    assert_eq!(content(&b), [0, 1, 2, 3]);
+}
+
+#[test]
+fn redo_selects_the_elements_it_brings_back() {
+    let mut b: VecHistoric<i32> = (0..6).collect();
+    b.select(1);
+    b.select(4);
+    b.remove_selects_historic();
+    b.undo();
+    b.deselect_all();
+
+    b.redo();
+    assert_eq!(content(&b), [0, 2, 3, 5]);
+    assert!(b.get_selected().is_empty());
+
+    b.undo();
+    assert_eq!(b.get_selected(), [&1, &4]);
+}
+
+#[test]
+fn redo_does_nothing_without_undone_entries() {
+    let mut b: VecHistoric<i32> = vec_historic![1];
+    assert!(b.redo().is_none());
+
+    b.push_back_historic(2);
+    assert!(b.redo().is_none());
+    assert_eq!(b.len_history(), 1);
+}
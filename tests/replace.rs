@@ -0,0 +1,87 @@
+mod common;
+
+use common::content;
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+#[test]
+fn guard_records_a_replace_when_dropped() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    {
+        let mut guard = b.get_mut_historic(1);
+        assert_eq!(*guard, 2);
+        *guard = 20;
+        *guard += 1;
+    }
+    assert_eq!(content(&b), [1, 21, 3]);
+    assert_eq!(b.len_history(), 1);
+
+    let entry = b.iter_history().last().unwrap();
+    assert!(matches!(entry.action, Action::Replace(data) if data.index == 1 && data.values == [2]));
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+    b.redo();
+    assert_eq!(content(&b), [1, 21, 3]);
+}
+
+#[test]
+fn untouched_guard_records_nothing() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    drop(b.get_mut_historic(0));
+    assert_eq!(*b.get_mut_historic(1), 2);
+    assert_eq!(b.len_history(), 0);
+}
+
+#[test]
+fn set_returns_the_old_value() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+
+    assert_eq!(*b.set_historic(2, 30), 3);
+    assert_eq!(*b.set_historic(2, 300), 30);
+    assert_eq!(content(&b), [1, 2, 300]);
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 30]);
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+
+    b.redo();
+    b.redo();
+    assert_eq!(content(&b), [1, 2, 300]);
+}
+
+#[test]
+fn replace_range_returns_the_replaced_values() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4];
+
+    assert_eq!(*b.replace_range_historic(1..4, [10, 20, 30]), [1, 2, 3]);
+    assert_eq!(*b.replace_range_historic(3.., [40, 50]), [30, 4]);
+    assert!(b.replace_range_historic(2..2, []).is_empty());
+    assert_eq!(content(&b), [0, 10, 20, 40, 50]);
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    assert_eq!(content(&b), [0, 10, 20, 30, 4]);
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3, 4]);
+
+    b.redo();
+    b.redo();
+    assert_eq!(content(&b), [0, 10, 20, 40, 50]);
+}
+
+#[test]
+#[should_panic(expected = "count of elements doesn't match the range")]
+fn replace_range_panics_on_count_mismatch() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    b.replace_range_historic(0..2, [7]);
+}
+
+#[test]
+#[should_panic]
+fn guard_panics_out_of_bounds() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+    b.get_mut_historic(3);
+}
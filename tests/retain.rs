@@ -0,0 +1,53 @@
+mod common;
+
+use common::content;
+use vec_historic::{VecHistoric, vec_historic};
+
+#[test]
+fn retain_records_one_removal() {
+    let mut b: VecHistoric<i32> = (0..10).collect();
+    b.select(4);
+    b.select(7);
+
+    b.retain_historic(|value| value % 3 != 0);
+    assert_eq!(content(&b), [1, 2, 4, 5, 7, 8]);
+    assert_eq!(b.get_selected(), [&4, &7]);
+    assert_eq!(b.len_history(), 1);
+
+    // nothing is recorded if every element is retained
+    b.retain_historic(|_| true);
+    assert_eq!(b.len_history(), 1);
+
+    b.undo();
+    assert_eq!(content(&b), (0..10).collect::<Vec<_>>());
+    b.redo();
+    assert_eq!(content(&b), [1, 2, 4, 5, 7, 8]);
+}
+
+#[test]
+fn dedup_by_key_keeps_the_first_of_a_run() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 1, 2, 3, 3, 3, 4, 5, 5];
+
+    b.dedup_by_key_historic(|value| *value);
+    assert_eq!(content(&b), [1, 2, 3, 4, 5]);
+
+    b.dedup_by_key_historic(|value| *value / 2);
+    assert_eq!(content(&b), [1, 2, 4]);
+    assert_eq!(b.len_history(), 2);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [1, 1, 2, 3, 3, 3, 4, 5, 5]);
+}
+
+#[test]
+fn extract_if_returns_the_removed_elements() {
+    let mut b: VecHistoric<i32> = (0..8).collect();
+
+    assert_eq!(b.extract_if_historic(|value| value % 2 == 1), [7, 5, 3, 1]);
+    assert_eq!(content(&b), [0, 2, 4, 6]);
+    assert!(b.extract_if_historic(|value| *value > 10).is_empty());
+
+    b.undo();
+    assert_eq!(content(&b), (0..8).collect::<Vec<_>>());
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::content;
+use vec_historic::{VecHistoric, VecHistoricError, vec_historic};
+
+#[test]
+fn undo_at_reverts_an_old_insert() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3];
+
+    b.insert_historic(1, 10);
+    b.push_front_historic(-1);
+    b.insert_many_historic(4, [20, 21]);
+    b.deselect_all();
+    b.pop_back_historic();
+    b.set_historic(6, 30);
+    assert_eq!(content(&b), [-1, 0, 10, 1, 20, 21, 30]);
+
+    assert!(b.undo_at(0).is_ok());
+    assert_eq!(content(&b), [-1, 0, 1, 20, 21, 30]);
+    assert_eq!(b.len_history(), 4);
+    assert!(b.validate_history().is_ok());
+
+    // later entries still undo their own changes
+    b.undo();
+    assert_eq!(content(&b), [-1, 0, 1, 20, 21, 2]);
+    b.undo();
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [0, 1, 2, 3]);
+
+    while b.redo().is_some() {}
+    assert_eq!(content(&b), [-1, 0, 1, 20, 21, 30]);
+}
+
+#[test]
+fn undo_at_puts_removed_elements_back() {
+    let mut b: VecHistoric<i32> = (0..6).collect();
+
+    b.select(2);
+    b.select(3);
+    b.remove_selects_historic();
+    b.insert_historic(2, 10);
+    b.pop_front_historic();
+
+    // the elements go back before the one inserted at their place
+    assert!(b.undo_at(0).is_ok());
+    assert_eq!(content(&b), [1, 2, 3, 10, 4, 5]);
+    assert_eq!(b.len_redo(), 0);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), (0..6).collect::<Vec<_>>());
+}
+
+#[test]
+fn undo_at_fails_on_dependent_entries() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2];
+
+    b.push_back_historic(3);
+    b.push_front_historic(-1);
+    b.set_historic(4, 30);
+    b.undo();
+    b.push_back_historic(4);
+
+    // the undone replacement isn't in history, the later push_front doesn't touch the pushed element
+    assert!(b.undo_at(0).is_ok());
+
+    b.set_historic(1, 10);
+    b.insert_historic(1, 20);
+    b.pop_front_historic();
+    b.deselect_all();
+    b.select(0);
+    b.remove_selects_historic();
+
+    let before = content(&b);
+    assert_eq!(content(&b), [10, 1, 2, 4]);
+
+    // the pushed front element and the inserted one are removed later
+    assert_eq!(b.undo_at(0), Err(VecHistoricError::Conflict { index: 4 }));
+    assert_eq!(b.undo_at(3), Err(VecHistoricError::Conflict { index: 5 }));
+    assert_eq!(b.undo_at(9), Err(VecHistoricError::IndexOutOfBounds { index: 9, len: 6 }));
+    assert_eq!(content(&b), before);
+    assert_eq!(b.len_history(), 6);
+}
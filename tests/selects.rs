@@ -0,0 +1,246 @@
+mod common;
+
+use common::sample;
+use vec_historic::VecHistoric;
+
+fn selected(b: &VecHistoric<i32>) -> Vec<i32> {
+    b.get_selected().into_iter().copied().collect()
+}
+
+fn indecies(b: &VecHistoric<i32>) -> Vec<usize> {
+    b.iter_selects().copied().collect()
+}
+
+#[test]
+fn pop_back_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.pop_back();
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.pop_back();
+    assert_eq!(selected(&b), [5, 2]);
+}
+
+#[test]
+fn pop_front_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.pop_front();
+    assert_eq!(indecies(&b), [4, 1, 7]);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn push_back_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.push_back(10);
+    assert_eq!(indecies(&b), [5, 2, 8]);
+}
+
+#[test]
+fn push_front_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.push_front(-1);
+    assert_eq!(indecies(&b), [6, 3, 9]);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn insert_shifts_selects_after_index() {
+    let mut b = sample(&[5, 2, 8]);
+    b.insert(5, 100);
+    assert_eq!(indecies(&b), [6, 2, 9]);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn insert_many_shifts_selects_after_index() {
+    let mut b = sample(&[5, 2, 8]);
+    b.insert_many(3, [100, 101, 102]);
+    assert_eq!(indecies(&b), [8, 2, 11]);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn remove_drops_select_of_removed_element() {
+    let mut b = sample(&[5, 2, 8]);
+    b.remove(3);
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.remove(4); // element 5
+    assert_eq!(selected(&b), [2, 8]);
+}
+
+#[test]
+fn clear_drops_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.clear();
+    assert_eq!(b.len_selects(), 0);
+}
+
+#[test]
+fn drain_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.drain(4..7).for_each(drop);
+    assert_eq!(selected(&b), [2, 8]);
+    assert_eq!(indecies(&b), [2, 5]);
+}
+
+#[test]
+fn extend_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.extend([10, 11]);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn pop_back_historic_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.pop_back_historic();
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn pop_front_historic_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.pop_front_historic();
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.undo();
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn push_back_historic_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.push_back_historic(10);
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.undo();
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn push_front_historic_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.push_front_historic(-1);
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.undo();
+    assert_eq!(indecies(&b), [5, 2, 8]);
+}
+
+#[test]
+fn insert_historic_selects_inserted_element() {
+    let mut b = sample(&[5, 2, 8]);
+    b.insert_historic(4, 100);
+    assert_eq!(selected(&b), [5, 2, 8, 100]);
+
+    b.undo();
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn insert_many_historic_selects_inserted_elements() {
+    let mut b = sample(&[5, 2, 8]);
+    b.insert_many_historic(0, [100, 101]);
+    assert_eq!(selected(&b), [5, 2, 8, 100, 101]);
+
+    b.undo();
+    assert_eq!(selected(&b), [5, 2, 8]);
+
+    b.redo();
+    assert_eq!(indecies(&b), [7, 4, 10, 0, 1]);
+}
+
+#[test]
+fn remove_selects_drops_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    assert_eq!(b.remove_selects(), [8, 5, 2]);
+    assert_eq!(b.len_selects(), 0);
+}
+
+#[test]
+fn remove_selects_historic_restores_selects_on_undo() {
+    let mut b = sample(&[5, 2, 8]);
+    b.remove_selects_historic();
+    assert_eq!(b.len_selects(), 0);
+
+    b.select(0);
+    b.undo();
+    assert_eq!(selected(&b), [0, 2, 5, 8]);
+}
+
+#[test]
+fn move_selects_selects_moved_elements() {
+    let mut b = sample(&[5, 2, 8]);
+    b.move_selects(0);
+    assert_eq!(selected(&b), [5, 2, 8]);
+    assert_eq!(indecies(&b), [1, 0, 2]);
+}
+
+#[test]
+fn move_selects_historic_selects_moved_elements() {
+    let mut b = sample(&[5, 2, 8]);
+    b.move_selects_historic(0);
+    assert_eq!(selected(&b), [5, 2, 8]);
+    assert_eq!(indecies(&b), [1, 0, 2]);
+}
+
+#[test]
+fn replace_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.deselect(2);
+    b.set_historic(2, 20);
+    assert_eq!(selected(&b), [5, 8]);
+
+    b.replace_range_historic(7..9, [70, 80]);
+    assert_eq!(selected(&b), [5, 80]);
+
+    *b.get_mut_historic(5) = 50;
+    assert_eq!(selected(&b), [50, 80]);
+
+    b.undo();
+    assert_eq!(selected(&b), [5, 80]);
+}
+
+#[test]
+fn group_undo_shifts_selects_through_every_action() {
+    let mut b = sample(&[5, 2, 8]);
+    b.begin_group();
+    b.push_front_historic(-1);
+    b.pop_back_historic();
+    b.insert_historic(0, -2);
+    b.commit_group();
+    b.deselect_all();
+    b.select(10); // element 8
+
+    b.undo();
+    assert_eq!(selected(&b), [8]);
+}
+
+#[test]
+fn aborted_transaction_keeps_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    let result: Result<(), ()> = b.transaction(|b| {
+        b.push_front_historic(-1);
+        b.pop_front_historic();
+        b.pop_front_historic();
+        Err(())
+    });
+
+    assert!(result.is_err());
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
+
+#[test]
+fn undo_tree_jump_shifts_selects() {
+    let mut b = sample(&[5, 2, 8]);
+    b.enable_undo_tree();
+
+    let root = b.root_node();
+    b.push_front_historic(-1);
+    b.push_front_historic(-2);
+
+    b.jump_to_node(root);
+    assert_eq!(selected(&b), [5, 2, 8]);
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::{Double, content};
+use vec_historic::{Action, VecHistoric, vec_historic};
+
+#[test]
+fn round_trip_keeps_data_selects_and_history() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3, 4, 5];
+    b.select(3);
+    b.select(1);
+    b.remove_selects_historic();
+    b.insert_many_historic(0, [10, 11]);
+    b.set_historic(4, 50);
+    b.push_front_historic(0);
+    b.undo();
+    b.deselect_all();
+    b.select(3);
+    b.select(0);
+
+    let json = serde_json::to_string(&b).unwrap();
+    let mut restored: VecHistoric<i32> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(content(&restored), content(&b));
+    assert_eq!(restored.iter_selects().collect::<Vec<_>>(), [&3, &0]);
+    assert_eq!(restored.len_history(), 3);
+    assert_eq!(restored.len_redo(), 1);
+    assert!(!restored.is_dirty());
+
+    restored.redo();
+    assert_eq!(content(&restored), [0, 10, 11, 1, 3, 50]);
+
+    while restored.len_history() > 0 {
+        restored.undo();
+    }
+    assert_eq!(content(&restored), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn actions_round_trip() {
+    let action: Action<i32> = Action::Group(vec![Action::PopBack(3), Action::Reinsert(vec![2, 0])]);
+
+    let json = serde_json::to_string(&action).unwrap();
+    let restored: Action<i32> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(format!("{restored:?}"), format!("{action:?}"));
+}
+
+#[test]
+fn inconsistent_history_is_rejected() {
+    let json = r#"{"data":[1,2],"selects":[],"history":[{"Insert":{"index":1,"amount":2}}]}"#;
+    assert!(serde_json::from_str::<VecHistoric<i32>>(json).is_err());
+
+    let json = r#"{"data":[1,2],"selects":[2],"history":[]}"#;
+    assert!(serde_json::from_str::<VecHistoric<i32>>(json).is_err());
+
+    let json = r#"{"data":[1,2],"selects":[1],"history":["PushBack","PushFront"]}"#;
+    assert!(serde_json::from_str::<VecHistoric<i32>>(json).is_ok());
+
+    let json = r#"{"data":[1],"selects":[],"history":["PushBack","PushFront"]}"#;
+    assert!(serde_json::from_str::<VecHistoric<i32>>(json).is_err());
+}
+
+#[test]
+fn overflowing_positions_are_rejected() {
+    let payloads = [
+        r#"{"data":[1,2],"selects":[],"history":[{"Replace":{"index":18446744073709551615,"values":[0]}}]}"#,
+        r#"{"data":[1,2],"selects":[],"history":[{"Move":{"dest_index":18446744073709551615,"indecies":[0]}}]}"#,
+        r#"{"data":[1,2],"selects":[],"history":[],"redo":[{"Permute":{"index":18446744073709551615,"order":[0]}}]}"#,
+    ];
+
+    for json in payloads {
+        assert!(serde_json::from_str::<VecHistoric<i32>>(json).is_err());
+    }
+}
+
+#[test]
+fn commands_in_history_fail_serialization() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.apply_command_historic(Double);
+
+    let error = serde_json::to_string(&b).unwrap_err();
+    assert!(error.to_string().contains("commands can't be serialized"));
+
+    // a command in the redo sequence isn't serialized either
+    b.undo();
+    assert!(serde_json::to_string(&b).is_err());
+
+    b.clear_history();
+    assert!(serde_json::to_string(&b).is_ok());
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use std::collections::VecDeque;
+
+use common::{Double, content};
+use vec_historic::{ChunkedVec, Storage, VecHistoric};
+
+/// Runs the same edits on a collection with storage `S`
+fn edit_and_undo<S: Storage<i32> + Clone>() {
+    let mut b: VecHistoric<i32, S> = (0..6).collect();
+
+    b.push_front_historic(-1);
+    b.insert_many_historic(3, [10, 11]);
+    b.deselect_all();
+    b.select(0);
+    b.select(5);
+    b.remove_selects_historic();
+    b.set_historic(1, 20);
+    b.drain(0..1).for_each(drop);
+    b.push_back(6);
+    assert_eq!(content(&b), [20, 10, 11, 3, 4, 5, 6]);
+    assert_eq!(b.len_history(), 0);
+
+    b.push_back_historic(7);
+    b.pop_front_historic();
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), [20, 10, 11, 3, 4, 5, 6]);
+
+    b.redo();
+    assert_eq!(content(&b), [20, 10, 11, 3, 4, 5, 6, 7]);
+    assert_eq!(b.clone().into_iter().rev().collect::<Vec<_>>(), [7, 6, 5, 4, 3, 11, 10, 20]);
+}
+
+#[test]
+fn every_storage_keeps_history() {
+    edit_and_undo::<gapbuf::GapBuffer<i32>>();
+    edit_and_undo::<Vec<i32>>();
+    edit_and_undo::<VecDeque<i32>>();
+    edit_and_undo::<ChunkedVec<i32>>();
+}
+
+/// Removes and moves scattered elements of a collection with storage `S` and reverts the changes
+fn scattered_selects<S: Storage<i32> + Clone>() {
+    let mut b: VecHistoric<i32, S> = (0..3000).collect();
+
+    for index in (1..3000).step_by(7) {
+        b.select(index);
+    }
+
+    b.move_selects_historic(1500);
+    // the block goes before the element 1500 and the 215 selected ones below it leave their places
+    assert_eq!(content(&b)[1285..1288], [1, 8, 15]);
+    assert_eq!(content(&b)[1285 + 429], 1500);
+
+    let removed: Vec<i32> = b.remove_selects_historic().clone();
+    assert_eq!(removed.len(), 429);
+    assert_eq!(removed[..2], [2997, 2990]);
+    assert_eq!(content(&b)[..3], [0, 2, 3]);
+
+    b.undo();
+    b.undo();
+    assert_eq!(content(&b), (0..3000).collect::<Vec<_>>());
+
+    b.redo();
+    b.redo();
+    assert_eq!(b.len(), 3000 - 429);
+    assert!(!content(&b).contains(&8));
+}
+
+#[test]
+fn every_storage_moves_scattered_selects() {
+    scattered_selects::<gapbuf::GapBuffer<i32>>();
+    scattered_selects::<Vec<i32>>();
+    scattered_selects::<VecDeque<i32>>();
+    scattered_selects::<ChunkedVec<i32>>();
+}
+
+#[test]
+fn chunked_storage_matches_vec() {
+    let mut b: VecHistoric<i32, ChunkedVec<i32>> = VecHistoric::from_data(ChunkedVec::new());
+    let mut expected: Vec<i32> = vec![];
+
+    // long insertions split chunks, removal across chunk bounds joins them back
+    b.insert_many_historic(0, 0..3000);
+    expected.extend(0..3000);
+    b.insert_many_historic(1000, 5000..6000);
+    expected.splice(1000..1000, 5000..6000);
+
+    for index in (0..4000).step_by(37).rev() {
+        b.insert_historic(index, -(index as i32));
+        expected.insert(index, -(index as i32));
+    }
+
+    b.undo();
+    b.undo();
+    expected.remove(0);
+    expected.remove(37);
+    assert_eq!(content(&b), expected);
+
+    let drained: Vec<i32> = b.drain(100..2500).collect();
+    assert_eq!(drained, expected.drain(100..2500).collect::<Vec<_>>());
+    assert_eq!(content(&b), expected);
+    assert_eq!(b[1234], expected[1234]);
+}
+
+#[test]
+fn chunked_tree_stays_consistent_through_random_edits() {
+    let mut chunked: ChunkedVec<u32> = ChunkedVec::new();
+    let mut expected: Vec<u32> = vec![];
+    let mut seed: u64 = 7;
+    let mut next = |bound: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % bound.max(1)
+    };
+
+    // enough elements for a tree three levels deep
+    chunked.extend(0..20000);
+    expected.extend(0..20000);
+
+    for step in 0..3000 {
+        let len = expected.len();
+
+        match next(6) {
+            0 | 1 => {
+                let index = next(len + 1);
+                Storage::insert(&mut chunked, index, step);
+                expected.insert(index, step);
+            }
+            2 if len > 0 => {
+                let index = next(len);
+                assert_eq!(Storage::remove(&mut chunked, index), expected.remove(index));
+            }
+            3 => {
+                let index = next(len + 1);
+                let values = step..step + next(1500) as u32;
+                chunked.insert_many(index, values.clone());
+                expected.splice(index..index, values);
+            }
+            _ => {
+                let start = next(len + 1);
+                let end = start + next((len - start).min(3000) + 1);
+                assert!(Storage::drain(&mut chunked, start..end).eq(expected.drain(start..end)));
+            }
+        }
+
+        assert_eq!(Storage::len(&chunked), expected.len());
+
+        if step % 100 == 0 {
+            assert!(Storage::iter(&chunked).eq(expected.iter()));
+            if !expected.is_empty() {
+                let index = next(expected.len());
+                assert_eq!(chunked[index], expected[index]);
+            }
+        }
+    }
+
+    Storage::iter_mut(&mut chunked).for_each(|value| *value += 1);
+    assert!(chunked.clone().into_iter().rev().eq(expected.iter().rev().map(|value| value + 1)));
+
+    Storage::drain(&mut chunked, 0..expected.len()).for_each(drop);
+    assert!(Storage::is_empty(&chunked));
+}
+
+#[test]
+fn storage_methods_are_not_ambiguous() {
+    use vec_historic::*;
+
+    let b: VecHistoric<i32, Vec<i32>> = VecHistoric::from_data(vec![1, 2, 3]);
+    assert_eq!(b.get_inner_data().len(), 3);
+    assert_eq!(b.get_inner_data().iter().sum::<i32>(), 6);
+    assert!(b.into_iter().eq([1, 2, 3]));
+}
+
+#[test]
+fn commands_see_any_storage() {
+    let mut b: VecHistoric<i32, VecDeque<i32>> = VecHistoric::from_data(VecDeque::from([1, 2, 3]));
+
+    b.apply_command_historic(Double);
+    assert_eq!(content(&b), [2, 4, 6]);
+
+    b.undo();
+    assert_eq!(content(&b), [1, 2, 3]);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::content;
+use vec_historic::{VecHistoric, vec_historic};
+
+#[test]
+fn new_action_after_undo_starts_a_branch() {
+    let mut b: VecHistoric<i32> = vec_historic![1, 2, 3];
+    b.enable_undo_tree();
+    let root = b.root_node();
+
+    b.push_back_historic(4);
+    let first = b.current_node();
+    b.undo();
+    b.push_back_historic(5);
+    let second = b.current_node();
+
+    assert!(b.is_undo_tree());
+    assert_eq!(b.node_children(root), [first, second]);
+    assert_eq!(b.node_parent(second), Some(root));
+    assert_eq!(b.branches(), [first, second]);
+
+    // redo follows the branch made last
+    b.undo();
+    b.redo();
+    assert_eq!(content(&b), [1, 2, 3, 5]);
+}
+
+#[test]
+fn branches_are_switched_between_siblings() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.enable_undo_tree();
+
+    b.push_back_historic(1);
+    b.undo();
+    b.push_back_historic(2);
+    b.undo();
+    b.push_back_historic(3);
+
+    assert!(b.next_branch().is_none());
+    b.prev_branch().unwrap();
+    assert_eq!(content(&b), [0, 2]);
+    b.prev_branch().unwrap();
+    assert_eq!(content(&b), [0, 1]);
+    assert!(b.prev_branch().is_none());
+    b.next_branch().unwrap();
+    assert_eq!(content(&b), [0, 2]);
+}
+
+#[test]
+fn jump_reaches_any_node() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.enable_undo_tree();
+
+    b.push_back_historic(1);
+    b.push_back_historic(2);
+    let deep = b.current_node();
+    b.undo();
+    b.undo();
+    b.insert_historic(0, -1);
+    b.push_back_historic(3);
+    let other = b.current_node();
+
+    assert!(b.jump_to_node(deep));
+    assert_eq!(content(&b), [0, 1, 2]);
+    assert_eq!(b.len_history(), 2);
+
+    assert!(b.jump_to_node(other));
+    assert_eq!(content(&b), [-1, 0, 3]);
+
+    assert!(b.jump_to_node(b.root_node()));
+    assert_eq!(content(&b), [0]);
+    assert_eq!(b.len_redo(), 2);
+
+    assert!(!b.jump_to_node(1000));
+}
+
+#[test]
+fn node_ids_survive_clear_history() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.enable_undo_tree();
+
+    b.push_back_historic(1);
+    b.undo();
+    b.push_back_historic(2);
+    let current = b.current_node();
+
+    assert_eq!(b.clear_history(), [1]);
+    assert_eq!(b.current_node(), current);
+    assert_eq!(b.root_node(), current);
+    assert_eq!(b.branches(), [current]);
+
+    b.push_back_historic(3);
+    assert_eq!(b.node_parent(b.current_node()), Some(current));
+}
+
+#[test]
+fn disabling_returns_elements_of_other_branches() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.enable_undo_tree();
+
+    b.push_back_historic(1);
+    b.pop_back_historic();
+    b.undo();
+    b.undo();
+    b.push_back_historic(2);
+
+    // the popped element is kept by the abandoned branch
+    assert_eq!(b.disable_undo_tree(), [1]);
+    assert!(!b.is_undo_tree());
+    assert!(b.branches().is_empty());
+
+    b.undo();
+    assert_eq!(content(&b), [0]);
+}
+
+#[test]
+fn linear_mode_has_no_tree() {
+    let mut b: VecHistoric<i32> = vec_historic![0];
+    b.push_back_historic(1);
+
+    assert!(b.node_children(b.root_node()).is_empty());
+    assert!(b.node_parent(b.current_node()).is_none());
+    assert!(!b.jump_to_node(b.root_node()));
+    assert!(b.next_branch().is_none());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::collections::VecDeque;
+
+use common::{Negate, content};
+use vec_historic::{VecHistoric, vec_historic};
+
+#[test]
+fn views_show_past_states() {
+    let mut b: VecHistoric<i32> = vec_historic![0, 1, 2, 3, 4];
+    let mut states: Vec<Vec<i32>> = vec![content(&b)];
+
+    b.insert_many_historic(2, [10, 11]);
+    states.push(content(&b));
+    b.deselect_all();
+    b.select(0);
+    b.select(4);
+    b.remove_selects_historic();
+    states.push(content(&b));
+    b.set_historic(1, 20);
+    states.push(content(&b));
+    b.select(0);
+    b.move_selects_historic(4);
+    states.push(content(&b));
+    b.begin_group();
+    b.pop_back_historic();
+    b.push_front_historic(30);
+    b.commit_group();
+    states.push(content(&b));
+
+    for steps in 0..states.len() {
+        let view = b.version_view(steps).unwrap();
+        let expected = &states[states.len() - 1 - steps];
+
+        assert_eq!(view.steps_back(), steps);
+        assert_eq!(view.len(), expected.len());
+        assert_eq!(view.iter().copied().collect::<Vec<_>>(), *expected);
+        assert_eq!(view.iter().rev().count(), expected.len());
+        assert_eq!(view[expected.len() - 1], expected[expected.len() - 1]);
+    }
+
+    assert!(b.version_view(states.len()).is_none());
+    assert_eq!(format!("{:?}", b.version_view(5).unwrap()), "[0, 1, 2, 3, 4]");
+}
+
+#[test]
+fn views_stop_at_commands() {
+    let mut b: VecHistoric<i32, VecDeque<i32>> = VecHistoric::from_data(VecDeque::from([1, 2]));
+
+    b.push_back_historic(3);
+    b.apply_command_historic(Negate { index: 0 });
+    b.push_front_historic(0);
+
+    assert_eq!(b.version_view(1).unwrap().get(0), Some(&-1));
+    assert!(b.version_view(1).unwrap().get(3).is_none());
+    assert!(b.version_view(2).is_none());
+}